@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS boats_fts_update;
+DROP TRIGGER IF EXISTS boats_fts_delete;
+DROP TRIGGER IF EXISTS boats_fts_insert;
+DROP TABLE boats_fts;
@@ -0,0 +1,29 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS boats_fts USING fts5(
+  name,
+  make,
+  model,
+  content = 'boats',
+  content_rowid = 'id',
+  tokenize = 'unicode61 remove_diacritics 2',
+  prefix = '2 3'
+);
+
+-- index any boats that existed before the search table
+INSERT INTO boats_fts(boats_fts) VALUES ('rebuild');
+
+CREATE TRIGGER IF NOT EXISTS boats_fts_insert AFTER INSERT ON boats BEGIN
+  INSERT INTO boats_fts(rowid, name, make, model)
+  VALUES (new.id, new.name, new.make, new.model);
+END;
+
+CREATE TRIGGER IF NOT EXISTS boats_fts_delete AFTER DELETE ON boats BEGIN
+  INSERT INTO boats_fts(boats_fts, rowid, name, make, model)
+  VALUES ('delete', old.id, old.name, old.make, old.model);
+END;
+
+CREATE TRIGGER IF NOT EXISTS boats_fts_update AFTER UPDATE ON boats BEGIN
+  INSERT INTO boats_fts(boats_fts, rowid, name, make, model)
+  VALUES ('delete', old.id, old.name, old.make, old.model);
+  INSERT INTO boats_fts(rowid, name, make, model)
+  VALUES (new.id, new.name, new.make, new.model);
+END;
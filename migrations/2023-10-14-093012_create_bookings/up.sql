@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS bookings (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+  starts_at TIMESTAMP NOT NULL,
+  ends_at TIMESTAMP NOT NULL,
+  CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS bookings_boat_id_starts_at ON bookings (boat_id, starts_at);
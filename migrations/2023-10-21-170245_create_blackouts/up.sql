@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS blackouts (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  uid TEXT NOT NULL,
+  summary TEXT,
+  starts_at TIMESTAMP NOT NULL,
+  ends_at TIMESTAMP NOT NULL,
+  CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS blackouts_boat_id_starts_at ON blackouts (boat_id, starts_at);
+CREATE INDEX IF NOT EXISTS blackouts_boat_id_uid ON blackouts (boat_id, uid);
@@ -0,0 +1,2 @@
+DROP TABLE seasonal_rates;
+DROP TABLE rate_cards;
@@ -0,0 +1,23 @@
+-- rates are in minor currency units, e.g. cents
+CREATE TABLE IF NOT EXISTS rate_cards (
+  boat_id INTEGER PRIMARY KEY NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  currency TEXT NOT NULL,
+  hourly_rate INTEGER,
+  daily_rate INTEGER,
+  weekly_rate INTEGER,
+  min_hours INTEGER NOT NULL DEFAULT 1,
+  CHECK (hourly_rate IS NOT NULL OR daily_rate IS NOT NULL OR weekly_rate IS NOT NULL)
+);
+
+CREATE TABLE IF NOT EXISTS seasonal_rates (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  starts_on DATE NOT NULL,
+  ends_on DATE NOT NULL,
+  multiplier REAL NOT NULL,
+  CHECK (ends_on >= starts_on),
+  CHECK (multiplier > 0)
+);
+
+CREATE INDEX IF NOT EXISTS seasonal_rates_boat_id ON seasonal_rates (boat_id, starts_on);
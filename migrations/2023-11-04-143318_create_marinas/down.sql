@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS boats_marina_id;
+ALTER TABLE boats DROP COLUMN marina_id;
+DROP TABLE marinas;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS marinas (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL,
+  latitude REAL NOT NULL,
+  longitude REAL NOT NULL,
+  timezone TEXT NOT NULL,
+  CHECK (latitude BETWEEN -90 AND 90),
+  CHECK (longitude BETWEEN -180 AND 180)
+);
+
+ALTER TABLE boats ADD COLUMN marina_id INTEGER REFERENCES marinas(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS boats_marina_id ON boats (marina_id);
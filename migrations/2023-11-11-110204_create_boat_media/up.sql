@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS boat_media (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  storage_key TEXT NOT NULL UNIQUE,
+  thumbnail_key TEXT UNIQUE,
+  file_name TEXT NOT NULL,
+  content_type TEXT NOT NULL,
+  size BIGINT NOT NULL,
+  checksum TEXT NOT NULL,
+  sort_order INTEGER NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS boat_media_boat_id ON boat_media (boat_id, sort_order);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS boats_owner_email;
+ALTER TABLE boats DROP COLUMN owner_email;
+ALTER TABLE users DROP COLUMN is_admin;
@@ -0,0 +1,6 @@
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT 0;
+
+-- boats created before ownership existed have no owner and can only be changed by admins
+ALTER TABLE boats ADD COLUMN owner_email TEXT REFERENCES users(email) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS boats_owner_email ON boats (owner_email);
@@ -0,0 +1 @@
+DROP TABLE boat_revisions;
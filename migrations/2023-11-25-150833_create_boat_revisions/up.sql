@@ -0,0 +1,11 @@
+-- no foreign key on boat_id, the history of a boat outlives the boat itself
+CREATE TABLE IF NOT EXISTS boat_revisions (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL,
+  action TEXT NOT NULL CHECK (action IN ('update', 'delete', 'revert')),
+  actor TEXT NOT NULL,
+  snapshot TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS boat_revisions_boat_id ON boat_revisions (boat_id, id);
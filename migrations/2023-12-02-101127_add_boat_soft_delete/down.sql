@@ -0,0 +1,16 @@
+CREATE TABLE boat_revisions_old (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL,
+  action TEXT NOT NULL CHECK (action IN ('update', 'delete', 'revert')),
+  actor TEXT NOT NULL,
+  snapshot TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+INSERT INTO boat_revisions_old
+  SELECT * FROM boat_revisions WHERE action IN ('update', 'delete', 'revert');
+DROP TABLE boat_revisions;
+ALTER TABLE boat_revisions_old RENAME TO boat_revisions;
+CREATE INDEX IF NOT EXISTS boat_revisions_boat_id ON boat_revisions (boat_id, id);
+
+DROP INDEX IF EXISTS boats_deleted_at;
+ALTER TABLE boats DROP COLUMN deleted_at;
@@ -0,0 +1,18 @@
+ALTER TABLE boats ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS boats_deleted_at ON boats (deleted_at);
+
+-- SQLite can't change a CHECK constraint in place, so the revisions table is rebuilt to allow
+-- the new actions
+CREATE TABLE boat_revisions_new (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL,
+  action TEXT NOT NULL CHECK (action IN ('update', 'delete', 'revert', 'restore', 'purge')),
+  actor TEXT NOT NULL,
+  snapshot TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL
+);
+INSERT INTO boat_revisions_new SELECT * FROM boat_revisions;
+DROP TABLE boat_revisions;
+ALTER TABLE boat_revisions_new RENAME TO boat_revisions;
+CREATE INDEX IF NOT EXISTS boat_revisions_boat_id ON boat_revisions (boat_id, id);
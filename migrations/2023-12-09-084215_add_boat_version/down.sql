@@ -0,0 +1 @@
+ALTER TABLE boats DROP COLUMN version;
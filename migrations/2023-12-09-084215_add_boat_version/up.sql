@@ -0,0 +1,2 @@
+-- bumped on every write so that clients can detect concurrent edits
+ALTER TABLE boats ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
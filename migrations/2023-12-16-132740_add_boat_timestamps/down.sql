@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS boats_updated_at;
+ALTER TABLE boats DROP COLUMN updated_at;
+ALTER TABLE boats DROP COLUMN created_at;
@@ -0,0 +1,9 @@
+-- SQLite can't add columns defaulting to the current time, so the timestamps are written by the
+-- application and existing boats start out at the time of the migration
+ALTER TABLE boats ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00+00:00';
+ALTER TABLE boats ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00+00:00';
+UPDATE boats SET
+  created_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'),
+  updated_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now');
+
+CREATE INDEX IF NOT EXISTS boats_updated_at ON boats (updated_at);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS maintenance_intervals;
+DROP TABLE IF EXISTS maintenance_windows;
+DROP TABLE IF EXISTS maintenance_records;
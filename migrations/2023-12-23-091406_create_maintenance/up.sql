@@ -0,0 +1,39 @@
+-- service work that has been carried out, cost is in minor units of its currency
+CREATE TABLE IF NOT EXISTS maintenance_records (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  service_type TEXT NOT NULL,
+  notes TEXT,
+  cost INTEGER,
+  currency TEXT,
+  engine_hours REAL,
+  performed_at TIMESTAMP NOT NULL,
+  CHECK ((cost IS NULL) = (currency IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS maintenance_records_boat_id_service_type
+  ON maintenance_records (boat_id, service_type, performed_at);
+
+-- periods in which a boat is out of service for scheduled work
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  service_type TEXT NOT NULL,
+  notes TEXT,
+  starts_at TIMESTAMP NOT NULL,
+  ends_at TIMESTAMP NOT NULL,
+  CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS maintenance_windows_boat_id_starts_at
+  ON maintenance_windows (boat_id, starts_at);
+
+-- how often each type of service is due, by elapsed days, engine hours or whichever comes first
+CREATE TABLE IF NOT EXISTS maintenance_intervals (
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  service_type TEXT NOT NULL,
+  every_days INTEGER,
+  every_engine_hours REAL,
+  PRIMARY KEY (boat_id, service_type),
+  CHECK (every_days IS NOT NULL OR every_engine_hours IS NOT NULL)
+);
@@ -0,0 +1,12 @@
+ALTER TABLE boats DROP COLUMN draft;
+ALTER TABLE boats DROP COLUMN max_passengers;
+ALTER TABLE boats DROP COLUMN cabins;
+ALTER TABLE boats DROP COLUMN berths;
+ALTER TABLE boats DROP COLUMN water_capacity;
+ALTER TABLE boats DROP COLUMN fuel_capacity;
+ALTER TABLE boats DROP COLUMN fuel_type;
+ALTER TABLE boats DROP COLUMN engine_power;
+ALTER TABLE boats DROP COLUMN engine_count;
+ALTER TABLE boats DROP COLUMN propulsion;
+ALTER TABLE boats DROP COLUMN hull_material;
+ALTER TABLE boats DROP COLUMN hull_type;
@@ -0,0 +1,14 @@
+-- allowed values of the text columns are checked by the application, so new ones can be added
+-- without rebuilding the table
+ALTER TABLE boats ADD COLUMN hull_type TEXT;
+ALTER TABLE boats ADD COLUMN hull_material TEXT;
+ALTER TABLE boats ADD COLUMN propulsion TEXT;
+ALTER TABLE boats ADD COLUMN engine_count INTEGER;
+ALTER TABLE boats ADD COLUMN engine_power REAL;
+ALTER TABLE boats ADD COLUMN fuel_type TEXT;
+ALTER TABLE boats ADD COLUMN fuel_capacity REAL;
+ALTER TABLE boats ADD COLUMN water_capacity REAL;
+ALTER TABLE boats ADD COLUMN berths INTEGER;
+ALTER TABLE boats ADD COLUMN cabins INTEGER;
+ALTER TABLE boats ADD COLUMN max_passengers INTEGER;
+ALTER TABLE boats ADD COLUMN draft REAL;
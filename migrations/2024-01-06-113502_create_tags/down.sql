@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS boat_tags_tag_id;
+DROP TABLE IF EXISTS boat_tags;
+DROP TABLE IF EXISTS tags;
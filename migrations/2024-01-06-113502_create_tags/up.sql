@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS tags (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL UNIQUE,
+  -- groups related tags, e.g. `type` for sailboat and catamaran
+  category TEXT,
+  description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS boat_tags (
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+  PRIMARY KEY (boat_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS boat_tags_tag_id ON boat_tags (tag_id);
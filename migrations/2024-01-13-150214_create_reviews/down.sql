@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS boats_rating;
+ALTER TABLE boats DROP COLUMN review_count;
+ALTER TABLE boats DROP COLUMN rating;
+DROP TABLE IF EXISTS reviews;
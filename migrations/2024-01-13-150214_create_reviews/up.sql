@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS reviews (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  boat_id INTEGER NOT NULL REFERENCES boats(id) ON DELETE CASCADE,
+  user_email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+  rating INTEGER NOT NULL,
+  text TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  UNIQUE (boat_id, user_email),
+  CHECK (rating BETWEEN 1 AND 5)
+);
+
+-- average rating and number of reviews, kept up to date with every review written so that
+-- listings can filter and sort by them like any other column
+ALTER TABLE boats ADD COLUMN rating REAL;
+ALTER TABLE boats ADD COLUMN review_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS boats_rating ON boats (rating);
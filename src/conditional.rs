@@ -0,0 +1,180 @@
+use crate::{config::get_config, errors::Error, units::Units};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use warp::{
+    http::{
+        header::{HeaderValue, CACHE_CONTROL, ETAG, LAST_MODIFIED},
+        StatusCode,
+    },
+    hyper::Body,
+    reply::{self, Response},
+    Reply,
+};
+
+// revalidate on every use, and keep responses for one API key out of shared caches
+const DEFAULT_CACHE_CONTROL: &str = "private, no-cache";
+
+// validators of a cached copy sent along with a GET
+pub struct Conditions {
+    pub if_none_match: Option<String>,
+    pub if_modified_since: Option<String>,
+}
+
+// strong entity tag of a versioned resource. Each unit system is a representation of its
+// own, metric ones keep the plain version
+pub fn etag(version: i32, units: Units) -> String {
+    match units {
+        Units::Metric => format!("\"{}\"", version),
+        Units::Imperial => format!("\"{}-{}\"", version, units.as_str()),
+    }
+}
+
+// weak entity tag of a computed representation, such as a page of a listing
+pub fn content_etag(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    let digest: String = hasher.finalize()[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("W/\"{}\"", digest)
+}
+
+// tag of a representation of the same version that reads differently, e.g. because of the
+// time it is read at
+pub fn variant_etag(etag: &str, variant: &str) -> String {
+    format!("{}-{}\"", etag.trim_end_matches('"'), variant)
+}
+
+pub fn with_etag(reply: impl Reply, version: i32, units: Units) -> impl Reply {
+    reply::with_header(reply, ETAG, etag(version, units))
+}
+
+// checks an If-Match header against the current version. Writes without the header go ahead
+// unless `preconditions.required` is set in the config
+pub fn check_if_match(if_match: Option<&str>, version: i32) -> Result<(), Error> {
+    let Some(if_match) = if_match else {
+        return if preconditions_required() {
+            Err(Error::PreconditionRequired)
+        } else {
+            Ok(())
+        };
+    };
+    // If-Match uses the strong comparison, so weak tags never match. Tags of every
+    // representation of the version name the same version
+    let current = version.to_string();
+    if if_match.split(',').map(str::trim).any(|tag| {
+        tag == "*"
+            || tag
+                .strip_prefix('"')
+                .and_then(|tag| tag.strip_suffix('"'))
+                .and_then(|tag| tag.split('-').next())
+                == Some(current.as_str())
+    }) {
+        Ok(())
+    } else {
+        Err(Error::PreconditionFailed)
+    }
+}
+
+// adds the cache headers to a GET response, or replaces it with 304 Not Modified when the
+// client's copy is still current
+pub fn cached(
+    response: Response,
+    conditions: &Conditions,
+    etag: &str,
+    last_modified: Option<DateTime<Utc>>,
+) -> Response {
+    let mut response = if is_fresh(conditions, etag, last_modified) {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response
+    } else {
+        response
+    };
+    let headers = response.headers_mut();
+    if let Ok(etag) = HeaderValue::from_str(etag) {
+        headers.insert(ETAG, etag);
+    }
+    if let Some(last_modified) = last_modified.and_then(|time| http_date(time).parse().ok()) {
+        headers.insert(LAST_MODIFIED, last_modified);
+    }
+    if let Ok(cache_control) = HeaderValue::from_str(&cache_control()) {
+        headers.insert(CACHE_CONTROL, cache_control);
+    }
+    response
+}
+
+// If-None-Match takes precedence, If-Modified-Since is only used by clients that send no tags
+fn is_fresh(conditions: &Conditions, etag: &str, last_modified: Option<DateTime<Utc>>) -> bool {
+    if let Some(if_none_match) = &conditions.if_none_match {
+        // If-None-Match uses the weak comparison
+        let current = etag.trim_start_matches("W/");
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current);
+    }
+    let since = conditions
+        .if_modified_since
+        .as_deref()
+        .and_then(|since| DateTime::parse_from_rfc2822(since).ok());
+    match (since, last_modified) {
+        // HTTP dates have whole seconds
+        (Some(since), Some(last_modified)) => last_modified.timestamp() <= since.timestamp(),
+        _ => false,
+    }
+}
+
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn cache_control() -> String {
+    get_config()
+        .get_string("cache.control")
+        .unwrap_or_else(|_| String::from(DEFAULT_CACHE_CONTROL))
+}
+
+fn preconditions_required() -> bool {
+    get_config()
+        .get_bool("preconditions.required")
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_systems_have_their_own_tags() {
+        let conditions = Conditions {
+            if_none_match: Some(etag(3, Units::Metric)),
+            if_modified_since: None,
+        };
+        assert!(is_fresh(&conditions, &etag(3, Units::Metric), None));
+        assert!(!is_fresh(&conditions, &etag(3, Units::Imperial), None));
+    }
+
+    #[test]
+    fn if_match_accepts_either_unit_system() {
+        assert!(check_if_match(Some("\"3\""), 3).is_ok());
+        assert!(check_if_match(Some("\"3-imperial\""), 3).is_ok());
+        assert!(check_if_match(Some("\"3-imperial-maintenance\""), 3).is_ok());
+        assert!(matches!(
+            check_if_match(Some("\"33\""), 3),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            check_if_match(Some("\"2-imperial\""), 3),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            check_if_match(Some("W/\"3\""), 3),
+            Err(Error::PreconditionFailed)
+        ));
+    }
+}
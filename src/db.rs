@@ -1,18 +1,34 @@
 use std::sync::Arc;
 
+use crate::geo::{haversine, haversine_km_utils};
 use anyhow::Result;
 use diesel::{
-    r2d2::{ConnectionManager, Pool, PooledConnection},
+    connection::SimpleConnection,
+    r2d2::{self, ConnectionManager, CustomizeConnection, Pool, PooledConnection},
     SqliteConnection,
 };
 use tokio::sync::Mutex;
 
 pub struct ConnectionPool(Pool<ConnectionManager<SqliteConnection>>);
 
+// SQLite settings and custom functions are per connection, so they are applied whenever the
+// pool opens one
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(r2d2::Error::QueryError)?;
+        haversine_km_utils::register_impl(conn, haversine).map_err(r2d2::Error::QueryError)
+    }
+}
+
 impl ConnectionPool {
     pub fn new(database_url: &str) -> Self {
         let manager = ConnectionManager::<SqliteConnection>::new(database_url);
         let pool = Pool::builder()
+            .connection_customizer(Box::new(ConnectionOptions))
             .build(manager)
             .expect("Failed to create connection pool");
         Self(pool)
@@ -24,3 +40,45 @@ impl ConnectionPool {
 }
 
 pub type SharedConnectionPool = Arc<Mutex<ConnectionPool>>;
+
+// an in-memory database with every migration applied, for tests that need real queries
+#[cfg(test)]
+pub fn test_connection() -> SqliteConnection {
+    use diesel::Connection;
+
+    let mut conn = SqliteConnection::establish(":memory:").expect("Failed to open test database");
+    ConnectionOptions
+        .on_acquire(&mut conn)
+        .expect("Failed to set up test database");
+    let mut migrations: Vec<_> =
+        std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+            .expect("Failed to read migrations")
+            .map(|entry| entry.expect("Failed to read migration").path())
+            .collect();
+    migrations.sort();
+    for migration in migrations {
+        let sql = std::fs::read_to_string(migration.join("up.sql")).expect("Failed to read up.sql");
+        conn.batch_execute(&sql).expect("Failed to run migration");
+    }
+    conn
+}
+
+#[cfg(test)]
+pub fn test_user(
+    conn: &mut SqliteConnection,
+    email: &str,
+    is_admin: bool,
+) -> crate::models::user::User {
+    use crate::{models::user::User, schema::users};
+    use diesel::{ExpressionMethods, RunQueryDsl, SelectableHelper};
+
+    diesel::insert_into(users::table)
+        .values((
+            users::email.eq(email),
+            users::api_key.eq(format!("key-{}", email)),
+            users::is_admin.eq(is_admin),
+        ))
+        .returning(User::as_returning())
+        .get_result(conn)
+        .expect("Failed to create test user")
+}
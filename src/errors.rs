@@ -1,11 +1,12 @@
-use crate::responses::ErrorResponse;
-use log::warn;
+use crate::{responses::ErrorResponse, validation::FieldError};
+use diesel::result::DatabaseErrorKind;
+use log::{error, warn};
 use std::convert::Infallible;
 use thiserror::Error;
 use warp::{
     filters::body::BodyDeserializeError,
     http::StatusCode,
-    reject::{self, MethodNotAllowed},
+    reject::{self, MethodNotAllowed, PayloadTooLarge},
     reply, Rejection, Reply,
 };
 
@@ -31,33 +32,107 @@ pub enum Error {
     NoCredit,
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
+    #[error("Conflicts with an existing resource")]
+    Conflict,
+    #[error("Referenced resource does not exist")]
+    InvalidReference,
+    #[error("File storage error")]
+    StorageFailed,
+    #[error("Unsupported media type")]
+    UnsupportedMediaType,
+    #[error("Payload too large")]
+    PayloadTooLarge,
+    #[error("Not acceptable")]
+    NotAcceptable,
+    #[error("Precondition failed")]
+    PreconditionFailed,
+    #[error("Precondition required")]
+    PreconditionRequired,
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
 }
 
 impl reject::Reject for Error {}
 
+impl From<diesel::result::Error> for Error {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => Error::NotFound,
+            diesel::result::Error::DatabaseError(kind, info) => match kind {
+                DatabaseErrorKind::UniqueViolation => Error::Conflict,
+                DatabaseErrorKind::ForeignKeyViolation => Error::InvalidReference,
+                DatabaseErrorKind::NotNullViolation | DatabaseErrorKind::CheckViolation => {
+                    Error::InvalidParameter
+                }
+                _ => {
+                    error!("Database error: {}", info.message());
+                    Error::ConnectionFailed
+                }
+            },
+            e => {
+                error!("Database error: {}", e);
+                Error::ConnectionFailed
+            }
+        }
+    }
+}
+
+impl Error {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Error::ConnectionFailed => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::NotFound => StatusCode::NOT_FOUND,
+            Error::InvalidParameter => StatusCode::BAD_REQUEST,
+            Error::NoPermission => StatusCode::FORBIDDEN,
+            Error::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            Error::JWTCreationFailed => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::MissingAPIKey => StatusCode::BAD_REQUEST,
+            Error::RateLimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+            Error::Conflict => StatusCode::CONFLICT,
+            Error::InvalidReference => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::StorageFailed => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Error::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            Error::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Error::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            Error::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    // message shown to clients, internal failures are not described
+    pub fn message(&self) -> String {
+        match self {
+            Error::JWTCreationFailed => "Internal server error".to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    // offending fields of a failed validation
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        match self {
+            Error::Validation(errors) => errors.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
     let (code, message) = if err.is_not_found() {
         (StatusCode::NOT_FOUND, String::from("Path not found"))
     } else if let Some(e) = err.find::<Error>() {
-        match e {
-            Error::ConnectionFailed => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-            Error::NotFound => (StatusCode::NOT_FOUND, e.to_string()),
-            Error::InvalidParameter => (StatusCode::BAD_REQUEST, e.to_string()),
-            Error::NoPermission => (StatusCode::FORBIDDEN, e.to_string()),
-            Error::InvalidCredentials => (StatusCode::UNAUTHORIZED, e.to_string()),
-            Error::JWTCreationFailed => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            ),
-            Error::MissingAPIKey => (StatusCode::BAD_REQUEST, e.to_string()),
-            Error::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, e.to_string()),
-            _ => (StatusCode::BAD_REQUEST, e.to_string()),
-        }
+        (e.status(), e.message())
     } else if err.find::<BodyDeserializeError>().is_some() {
         (
             StatusCode::BAD_REQUEST,
             String::from("Invalid JSON body or missing field"),
         )
+    } else if err.find::<PayloadTooLarge>().is_some() {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            String::from("Payload too large"),
+        )
     } else if err.find::<MethodNotAllowed>().is_some() {
         (
             StatusCode::METHOD_NOT_ALLOWED,
@@ -71,9 +146,14 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
         )
     };
 
+    let errors = err
+        .find::<Error>()
+        .map(Error::field_errors)
+        .unwrap_or_default();
     let json = reply::json(&ErrorResponse {
         status: code.to_string(),
         message,
+        errors,
     });
     Ok(reply::with_status(json, code))
 }
@@ -0,0 +1,143 @@
+use crate::errors::Error;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    // in order of preference when the client accepts several equally
+    const ALL: [ExportFormat; 3] = [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Ndjson];
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    fn mime(self) -> (&'static str, &'static str) {
+        match self {
+            ExportFormat::Json => ("application", "json"),
+            ExportFormat::Csv => ("text", "csv"),
+            ExportFormat::Ndjson => ("application", "x-ndjson"),
+        }
+    }
+}
+
+// picks the format with the highest quality in an `Accept` header, the most specific media
+// range decides the quality of each format
+pub fn negotiate(accept: Option<&str>) -> Result<ExportFormat, Error> {
+    let accept = match accept.map(str::trim) {
+        None | Some("") => return Ok(ExportFormat::Json),
+        Some(accept) => accept,
+    };
+    let ranges: Vec<(&str, &str, f32)> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let (kind, subtype) = params.next()?.trim().split_once('/')?;
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((kind, subtype, quality))
+        })
+        .collect();
+
+    let mut best: Option<(ExportFormat, f32)> = None;
+    for format in ExportFormat::ALL {
+        let (kind, subtype) = format.mime();
+        let quality = ranges
+            .iter()
+            .filter_map(|&(range_kind, range_subtype, quality)| {
+                let specificity = match (range_kind, range_subtype) {
+                    (k, s) if k.eq_ignore_ascii_case(kind) && s.eq_ignore_ascii_case(subtype) => 2,
+                    (k, "*") if k.eq_ignore_ascii_case(kind) => 1,
+                    ("*", "*") => 0,
+                    _ => return None,
+                };
+                Some((specificity, quality))
+            })
+            .max_by_key(|&(specificity, _)| specificity)
+            .map(|(_, quality)| quality);
+        if let Some(quality) = quality.filter(|quality| *quality > 0.0) {
+            if best.is_none_or(|(_, best)| quality > best) {
+                best = Some((format, quality));
+            }
+        }
+    }
+    best.map(|(format, _)| format).ok_or(Error::NotAcceptable)
+}
+
+// serializes rows one at a time, CSV columns are taken from the fields of the first row
+pub struct RowWriter {
+    format: ExportFormat,
+    rows: usize,
+}
+
+impl RowWriter {
+    pub fn new(format: ExportFormat) -> Self {
+        Self { format, rows: 0 }
+    }
+
+    pub fn row(&mut self, row: &impl Serialize, out: &mut Vec<u8>) -> Result<(), Error> {
+        let first = self.rows == 0;
+        self.rows += 1;
+        match self.format {
+            ExportFormat::Json => {
+                out.push(if first { b'[' } else { b',' });
+                serde_json::to_writer(out, row).map_err(|_| Error::InvalidParameter)
+            }
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *out, row).map_err(|_| Error::InvalidParameter)?;
+                out.push(b'\n');
+                Ok(())
+            }
+            ExportFormat::Csv => {
+                let Value::Object(fields) =
+                    serde_json::to_value(row).map_err(|_| Error::InvalidParameter)?
+                else {
+                    return Err(Error::InvalidParameter);
+                };
+                let mut writer = csv::Writer::from_writer(out);
+                if first {
+                    writer
+                        .write_record(fields.keys())
+                        .map_err(|_| Error::InvalidParameter)?;
+                }
+                writer
+                    .write_record(fields.values().map(csv_field))
+                    .map_err(|_| Error::InvalidParameter)?;
+                writer.flush().map_err(|_| Error::InvalidParameter)
+            }
+        }
+    }
+
+    pub fn finish(self, out: &mut Vec<u8>) {
+        if self.format == ExportFormat::Json {
+            out.extend_from_slice(if self.rows == 0 { b"[]" } else { b"]" });
+        }
+    }
+}
+
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,17 @@
+use diesel::{define_sql_function, sql_types::Double};
+
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+define_sql_function! {
+    // implemented by `haversine`, which `db` registers on every connection
+    fn haversine_km(lat1: Double, lon1: Double, lat2: Double, lon2: Double) -> Double;
+}
+
+// great-circle distance in kilometres between two points given in degrees
+pub fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
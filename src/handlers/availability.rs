@@ -0,0 +1,265 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::acquire_connection,
+    models::availability::{Availability, AvailabilityQuery, DayStatus, Granularity, Interval},
+    schema::{blackouts, boats, bookings, maintenance_windows},
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use std::collections::BTreeMap;
+use warp::{reject, reply};
+
+const MAX_WINDOW_DAYS: i64 = 366;
+
+pub async fn get_availability(
+    boat_id: i32,
+    query: AvailabilityQuery,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (from, to) = (query.from, query.to);
+    if from >= to || to - from > Duration::days(MAX_WINDOW_DAYS) {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+
+    let mut conn = acquire_connection(&pool).await?;
+    let is_available: i32 = boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::is_available)
+        .first(&mut conn)
+        .map_err(|_| reject::custom(Error::NotFound))?;
+
+    // a boat that has been manually taken out of service is busy for the whole window,
+    // otherwise it is busy whenever it is booked, blocked or in maintenance
+    let busy = if is_available == 0 {
+        vec![Interval {
+            starts_at: from,
+            ends_at: to,
+        }]
+    } else {
+        let mut periods: Vec<(DateTime<Utc>, DateTime<Utc>)> = bookings::table
+            .filter(bookings::boat_id.eq(boat_id))
+            .filter(bookings::starts_at.lt(to))
+            .filter(bookings::ends_at.gt(from))
+            .select((bookings::starts_at, bookings::ends_at))
+            .load(&mut conn)
+            .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+        periods.extend(
+            blackouts::table
+                .filter(blackouts::boat_id.eq(boat_id))
+                .filter(blackouts::starts_at.lt(to))
+                .filter(blackouts::ends_at.gt(from))
+                .select((blackouts::starts_at, blackouts::ends_at))
+                .load::<(DateTime<Utc>, DateTime<Utc>)>(&mut conn)
+                .map_err(|_| reject::custom(Error::ConnectionFailed))?,
+        );
+        periods.extend(
+            maintenance_windows::table
+                .filter(maintenance_windows::boat_id.eq(boat_id))
+                .filter(maintenance_windows::starts_at.lt(to))
+                .filter(maintenance_windows::ends_at.gt(from))
+                .select((maintenance_windows::starts_at, maintenance_windows::ends_at))
+                .load::<(DateTime<Utc>, DateTime<Utc>)>(&mut conn)
+                .map_err(|_| reject::custom(Error::ConnectionFailed))?,
+        );
+        merge_intervals(
+            periods
+                .into_iter()
+                .map(|(starts_at, ends_at)| Interval { starts_at, ends_at })
+                .collect(),
+            from,
+            to,
+        )
+    };
+
+    let availability = match query.granularity.unwrap_or(Granularity::Interval) {
+        Granularity::Interval => Availability {
+            boat_id,
+            from,
+            to,
+            free: Some(free_intervals(&busy, from, to)),
+            busy: Some(busy),
+            days: None,
+        },
+        Granularity::Day => Availability {
+            boat_id,
+            from,
+            to,
+            free: None,
+            busy: None,
+            days: Some(daily_status(&busy, from, to)),
+        },
+    };
+    Ok(reply::json(&availability))
+}
+
+// clips intervals to the window, then sorts and coalesces overlapping or touching ones
+fn merge_intervals(
+    mut intervals: Vec<Interval>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<Interval> {
+    intervals.sort_by_key(|interval| interval.starts_at);
+    let mut merged: Vec<Interval> = Vec::new();
+    for interval in intervals {
+        let starts_at = interval.starts_at.max(from);
+        let ends_at = interval.ends_at.min(to);
+        if starts_at >= ends_at {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if starts_at <= last.ends_at => last.ends_at = last.ends_at.max(ends_at),
+            _ => merged.push(Interval { starts_at, ends_at }),
+        }
+    }
+    merged
+}
+
+// gaps between the merged busy intervals within the window
+fn free_intervals(busy: &[Interval], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Interval> {
+    let mut free = Vec::new();
+    let mut cursor = from;
+    for interval in busy {
+        if interval.starts_at > cursor {
+            free.push(Interval {
+                starts_at: cursor,
+                ends_at: interval.starts_at,
+            });
+        }
+        cursor = interval.ends_at;
+    }
+    if cursor < to {
+        free.push(Interval {
+            starts_at: cursor,
+            ends_at: to,
+        });
+    }
+    free
+}
+
+fn daily_status(
+    busy: &[Interval],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> BTreeMap<NaiveDate, DayStatus> {
+    let mut days = BTreeMap::new();
+    let mut date = from.date_naive();
+    while let Some(day_start) = date.and_hms_opt(0, 0, 0).map(|day| day.and_utc()) {
+        if day_start >= to {
+            break;
+        }
+        // the first and last days may only be partly inside the window
+        let starts_at = day_start.max(from);
+        let ends_at = (day_start + Duration::days(1)).min(to);
+        let busy_time = busy
+            .iter()
+            .map(|interval| interval.ends_at.min(ends_at) - interval.starts_at.max(starts_at))
+            .filter(|overlap| *overlap > Duration::zero())
+            .fold(Duration::zero(), |total, overlap| total + overlap);
+        let status = if busy_time.is_zero() {
+            DayStatus::Free
+        } else if busy_time >= ends_at - starts_at {
+            DayStatus::Busy
+        } else {
+            DayStatus::Partial
+        };
+        days.insert(date, status);
+        date = match date.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn interval(starts_at: &str, ends_at: &str) -> Interval {
+        Interval {
+            starts_at: at(starts_at),
+            ends_at: at(ends_at),
+        }
+    }
+
+    fn spans(intervals: &[Interval]) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        intervals
+            .iter()
+            .map(|interval| (interval.starts_at, interval.ends_at))
+            .collect()
+    }
+
+    #[test]
+    fn merges_overlapping_and_touching_intervals() {
+        let busy = merge_intervals(
+            vec![
+                interval("2024-06-05T10:00:00Z", "2024-06-06T10:00:00Z"),
+                interval("2024-06-01T00:00:00Z", "2024-06-02T12:00:00Z"),
+                interval("2024-06-02T12:00:00Z", "2024-06-03T00:00:00Z"),
+                interval("2024-06-05T12:00:00Z", "2024-06-05T14:00:00Z"),
+                interval("2024-06-20T00:00:00Z", "2024-06-21T00:00:00Z"),
+            ],
+            at("2024-06-01T06:00:00Z"),
+            at("2024-06-10T00:00:00Z"),
+        );
+        // clipped to the window, and intervals outside it are dropped
+        assert_eq!(
+            spans(&busy),
+            [
+                (at("2024-06-01T06:00:00Z"), at("2024-06-03T00:00:00Z")),
+                (at("2024-06-05T10:00:00Z"), at("2024-06-06T10:00:00Z")),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_the_gaps_between_busy_intervals() {
+        let (from, to) = (at("2024-06-01T00:00:00Z"), at("2024-06-10T00:00:00Z"));
+        let busy = [
+            interval("2024-06-01T00:00:00Z", "2024-06-03T00:00:00Z"),
+            interval("2024-06-05T10:00:00Z", "2024-06-06T10:00:00Z"),
+        ];
+        assert_eq!(
+            spans(&free_intervals(&busy, from, to)),
+            [
+                (at("2024-06-03T00:00:00Z"), at("2024-06-05T10:00:00Z")),
+                (at("2024-06-06T10:00:00Z"), to),
+            ]
+        );
+        assert_eq!(spans(&free_intervals(&[], from, to)), [(from, to)]);
+        let all = [interval("2024-06-01T00:00:00Z", "2024-06-10T00:00:00Z")];
+        assert!(free_intervals(&all, from, to).is_empty());
+    }
+
+    #[test]
+    fn rates_each_day_of_the_window() {
+        let busy = [
+            interval("2024-06-01T00:00:00Z", "2024-06-02T00:00:00Z"),
+            interval("2024-06-03T10:00:00Z", "2024-06-03T12:00:00Z"),
+        ];
+        // the first and last days are only partly in the window
+        let days = daily_status(
+            &busy,
+            at("2024-06-01T12:00:00Z"),
+            at("2024-06-04T06:00:00Z"),
+        );
+        let days: Vec<(String, DayStatus)> = days
+            .into_iter()
+            .map(|(date, status)| (date.to_string(), status))
+            .collect();
+        assert!(
+            days == [
+                (String::from("2024-06-01"), DayStatus::Busy),
+                (String::from("2024-06-02"), DayStatus::Free),
+                (String::from("2024-06-03"), DayStatus::Partial),
+                (String::from("2024-06-04"), DayStatus::Free),
+            ]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::{
+        boat::{insert_boat, replace_boat, trash_boat},
+        helpers::acquire_connection,
+    },
+    models::{
+        batch::{BatchOperation, BatchQuery, BatchReport, BatchResult},
+        boat::Boat,
+        user::User,
+    },
+    units::{label_units, Units},
+};
+use diesel::{Connection, SqliteConnection};
+use serde_json::Value;
+use warp::{http::StatusCode, reject, reply};
+
+const MAX_OPERATIONS: usize = 500;
+
+// runs the operations in order in a single transaction. Without `atomic` every operation gets a
+// savepoint, so failed ones are reported and the rest are committed
+pub async fn run_batch(
+    query: BatchQuery,
+    units: Units,
+    operations: Vec<Value>,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if operations.len() > MAX_OPERATIONS {
+        return Err(reject::custom(Error::PayloadTooLarge));
+    }
+    let atomic = query.atomic.unwrap_or(false);
+    let count = operations.len();
+
+    let mut conn = acquire_connection(&pool).await?;
+    let mut results = Vec::with_capacity(count);
+    let outcome = conn.immediate_transaction::<(), BatchError, _>(|conn| {
+        for (index, operation) in operations.into_iter().enumerate() {
+            let result = if atomic {
+                run(conn, operation, units, &user)
+            } else {
+                conn.transaction(|conn| run(conn, operation, units, &user))
+            };
+            let failed = result.is_err();
+            results.push(batch_result(index, result));
+            if failed && atomic {
+                return Err(BatchError::RolledBack);
+            }
+        }
+        Ok(())
+    });
+
+    let committed = match outcome {
+        Ok(()) => true,
+        Err(BatchError::RolledBack) => {
+            // only the failed operation keeps its result, the others were undone or never run
+            let failed = results.pop();
+            results = (0..count)
+                .map(|index| BatchResult {
+                    index,
+                    status: StatusCode::FAILED_DEPENDENCY.as_u16(),
+                    boat: None,
+                    error: Some(String::from("Batch rolled back")),
+                    errors: Vec::new(),
+                })
+                .collect();
+            if let Some(failed) = failed {
+                let index = failed.index;
+                results[index] = failed;
+            }
+            false
+        }
+        Err(BatchError::Database(e)) => return Err(reject::custom(e)),
+    };
+    Ok(label_units(
+        reply::json(&BatchReport {
+            atomic,
+            committed,
+            results,
+        }),
+        units,
+    ))
+}
+
+// why a batch transaction ended early
+enum BatchError {
+    // an operation of an atomic batch failed, its result says why
+    RolledBack,
+    // the transaction itself failed
+    Database(Error),
+}
+
+impl From<diesel::result::Error> for BatchError {
+    fn from(e: diesel::result::Error) -> Self {
+        BatchError::Database(Error::from(e))
+    }
+}
+
+fn run(
+    conn: &mut SqliteConnection,
+    operation: Value,
+    units: Units,
+    user: &User,
+) -> Result<(StatusCode, Option<Boat>), Error> {
+    let operation: BatchOperation =
+        serde_json::from_value(operation).map_err(|_| Error::InvalidParameter)?;
+    match operation {
+        BatchOperation::Create { boat } => {
+            let boat = insert_boat(conn, boat.into_metric(units), user)?;
+            Ok((StatusCode::CREATED, Some(boat.in_units(units))))
+        }
+        BatchOperation::Update { id, boat, if_match } => {
+            let boat = replace_boat(conn, id, if_match.as_deref(), user, |_| {
+                Ok(boat.into_metric(units))
+            })?;
+            Ok((StatusCode::OK, Some(boat.in_units(units))))
+        }
+        BatchOperation::Delete { id, if_match } => {
+            trash_boat(conn, id, if_match.as_deref(), user)?;
+            Ok((StatusCode::NO_CONTENT, None))
+        }
+    }
+}
+
+fn batch_result(index: usize, result: Result<(StatusCode, Option<Boat>), Error>) -> BatchResult {
+    match result {
+        Ok((status, boat)) => BatchResult {
+            index,
+            status: status.as_u16(),
+            boat,
+            error: None,
+            errors: Vec::new(),
+        },
+        Err(e) => BatchResult {
+            index,
+            status: e.status().as_u16(),
+            boat: None,
+            error: Some(e.message()),
+            errors: e.field_errors(),
+        },
+    }
+}
@@ -1,80 +1,1377 @@
 use crate::{
+    conditional::{
+        cached, check_if_match, content_etag, etag, variant_etag, with_etag, Conditions,
+    },
+    config::get_config,
     db::SharedConnectionPool,
     errors::Error,
-    handlers::helpers::acquire_connection,
-    models::boat::{Boat, NewBoat, UpdateBoat},
-    schema::boats,
+    export::{negotiate, ExportFormat, RowWriter},
+    geo::haversine_km,
+    handlers::{
+        self,
+        helpers::{acquire_connection, authorize_boat_owner, check_owner},
+        revision::record_revision,
+    },
+    models::{
+        boat::{
+            Boat, BoatCursor, BoatListing, BoatQuery, BoatSearchCount, BoatSearchQuery,
+            BoatSearchResult, BoatSort, BoatState, NewBoat, SortDirection, SortField, SortKey,
+            FUEL_TYPES, HULL_MATERIALS, HULL_TYPES, PROPULSION_TYPES,
+        },
+        marina::GeoPoint,
+        review::{MAX_RATING, MIN_RATING},
+        revision::RevisionAction,
+        tag::TagMatch,
+        user::User,
+    },
+    pagination::{
+        decode_cursor, encode_cursor, link_header, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+        TOTAL_COUNT_HEADER,
+    },
+    patch::{json_patch, merge_patch, JSON_PATCH, MERGE_PATCH},
+    responses::PurgeResponse,
+    schema::{
+        blackouts, boat_media, boat_tags, boats, bookings, maintenance_windows, marinas, tags,
+    },
+    search::{fts_query, HIGHLIGHT_END, HIGHLIGHT_START},
+    storage::SharedStorage,
+    units::{label_units, Units},
+    validation::{Validator, INVALID_VALUE, REQUIRED},
+};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{
+    connection::DefaultLoadingMode,
+    dsl::{exists, not},
+    expression::BoxableExpression,
+    r2d2::{ConnectionManager, PooledConnection},
+    sql_types::{BigInt, Bool, Double, Nullable, Text},
+    sqlite::{Sqlite, SqliteConnection},
+    BoolExpressionMethods, ExpressionMethods, NullableExpressionMethods, OptionalExtension,
+    QueryDsl, RunQueryDsl, SelectableHelper,
 };
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 use log::error;
-use warp::{http::StatusCode, reject, reply};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use warp::{
+    http::{
+        header::{HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE, LINK, LOCATION},
+        StatusCode,
+    },
+    hyper::{body::Bytes, Body},
+    reject,
+    reply::{self, Response},
+    Reply,
+};
+
+const DEFAULT_RADIUS_KM: f64 = 25.0;
+const MAX_RADIUS_KM: f64 = 20_000.0;
+// fields a patched boat can't do without
+const REQUIRED_FIELDS: [&str; 5] = ["name", "make", "model", "year", "is_available"];
+const EXPORT_CHUNK_SIZE: usize = 16 * 1024;
+const EXPORT_CHANNEL_SIZE: usize = 16;
+// how long deleted boats stay in the trash unless `trash.retention_days` is configured
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+const MAX_TAG_FILTERS: usize = 20;
+// entity tag variant of a boat in a maintenance window
+const MAINTENANCE: &str = "maintenance";
 
 pub async fn get_boat(
     id: i32,
+    conditions: Conditions,
+    units: Units,
     pool: SharedConnectionPool,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut conn = acquire_connection(&pool).await?;
-    let boat: Boat = boats::table
+    let (boat, in_maintenance): (Boat, bool) = boats::table
         .find(&id)
+        .filter(boats::deleted_at.is_null())
+        .select((boats::all_columns, in_maintenance(Utc::now())))
         .first(&mut conn)
-        .map_err(|_| reject::custom(Error::NotFound))?;
-    Ok(reply::json(&boat))
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    // a maintenance window starting or ending changes the boat without a new version, so
+    // the boat is tagged apart while it lasts, and can't be revalidated by its date
+    let (etag, last_modified) = if in_maintenance {
+        (variant_etag(&etag(boat.version, units), MAINTENANCE), None)
+    } else {
+        (etag(boat.version, units), Some(boat.updated_at))
+    };
+    Ok(label_units(
+        cached(
+            reply::json(&available(boat, in_maintenance).in_units(units)).into_response(),
+            &conditions,
+            &etag,
+            last_modified,
+        ),
+        units,
+    ))
 }
 
 pub async fn get_all_boats(
+    query: BoatQuery,
+    raw_query: String,
+    accept: Option<String>,
+    conditions: Conditions,
+    units: Units,
     pool: SharedConnectionPool,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let query = query.into_metric(units);
+    let format = negotiate(accept.as_deref()).map_err(reject::custom)?;
+    let export = format != ExportFormat::Json || query.download == Some(true);
+    // exports contain every matching boat, so they can't be paginated
+    if export && (query.limit.is_some() || query.offset.is_some() || query.cursor.is_some()) {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    if query
+        .year_min
+        .zip(query.year_max)
+        .is_some_and(|(min, max)| min > max)
+        || query
+            .length_min
+            .zip(query.length_max)
+            .is_some_and(|(min, max)| min > max)
+        || query.min_rating.is_some_and(|rating| {
+            !(f64::from(MIN_RATING)..=f64::from(MAX_RATING)).contains(&rating)
+        })
+    {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let tags = query.tags();
+    if tags.len() > MAX_TAG_FILTERS
+        || tags.iter().any(|tag| tag.is_empty())
+        || query.tag.is_none() && query.tag_match.is_some()
+    {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    // an unknown specification value would silently match nothing
+    for (value, allowed) in [
+        (&query.hull_type, HULL_TYPES),
+        (&query.hull_material, HULL_MATERIALS),
+        (&query.propulsion, PROPULSION_TYPES),
+        (&query.fuel_type, FUEL_TYPES),
+    ] {
+        if value
+            .as_deref()
+            .is_some_and(|value| !allowed.contains(&value))
+        {
+            return Err(reject::custom(Error::InvalidParameter));
+        }
+    }
+    match (query.available_from, query.available_to) {
+        (Some(from), Some(to)) if from < to => {}
+        (None, None) => {}
+        _ => return Err(reject::custom(Error::InvalidParameter)),
+    }
+    // location searches are always ordered by distance
+    if query.near.is_none() && query.radius_km.is_some()
+        || query.near.is_some() && (query.sort.is_some() || query.cursor.is_some())
+        || query
+            .radius_km
+            .is_some_and(|radius| !(radius > 0.0 && radius <= MAX_RADIUS_KM))
+    {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let sort = query.sort.unwrap_or_default();
+    let cursor = match &query.cursor {
+        Some(cursor) => {
+            let cursor: BoatCursor = decode_cursor(cursor).map_err(reject::custom)?;
+            // a cursor is only meaningful for the ordering it was issued for
+            if query.offset.is_some() || cursor.sort != sort {
+                return Err(reject::custom(Error::InvalidParameter));
+            }
+            Some(cursor)
+        }
+        None => None,
+    };
+
     let mut conn = acquire_connection(&pool).await?;
-    let boats: Vec<Boat> = boats::table
-        .load(&mut conn)
-        .map_err(|e| error!("{}", e.to_string()))
-        .map_err(|_| reject::custom(Error::NotFound))?;
-    Ok(reply::json(&boats))
+    let total: i64 = filter_boats(&query)
+        .count()
+        .get_result(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+
+    if export {
+        return Ok(label_units(
+            export_boats(query, sort, format, units, total, conn),
+            units,
+        ));
+    }
+    // every write to any boat moves this forward, including the ones removing a boat from
+    // the listing. Availability also depends on bookings, blackouts and maintenance windows,
+    // and tags are attached without writing the boat, so such listings can only be
+    // revalidated by their ETag
+    let last_modified: Option<DateTime<Utc>> =
+        if query.is_available.is_some() || query.available_from.is_some() || query.tag.is_some() {
+            None
+        } else {
+            boats::table
+                .select(boats::updated_at)
+                .order(boats::updated_at.desc())
+                .first(&mut conn)
+                .optional()
+                .map_err(|e| reject::custom(Error::from(e)))?
+        };
+
+    let now = Utc::now();
+    if let Some(near) = query.near {
+        let boats: Vec<(Boat, Option<f64>, bool)> = filter_boats(&query)
+            .select((boats::all_columns, distance_km(near), in_maintenance(now)))
+            .order((distance_km(near).asc(), boats::id.asc()))
+            .limit(limit.into())
+            .offset(query.offset.unwrap_or(0).into())
+            .load(&mut conn)
+            .map_err(|e| reject::custom(Error::from(e)))?;
+        let boats: Vec<BoatListing> = boats
+            .into_iter()
+            .map(|(boat, distance_km, in_maintenance)| BoatListing {
+                boat: available(boat, in_maintenance).in_units(units),
+                distance_km,
+            })
+            .collect();
+        return listing_page(&boats, total, None, &conditions, last_modified)
+            .map(|response| label_units(response, units))
+            .map_err(reject::custom);
+    }
+
+    let (boats, links) = sorted_page(&mut conn, &query, sort, cursor.as_ref(), limit, now)
+        .map_err(reject::custom)?;
+    let links = if links.is_empty() {
+        None
+    } else {
+        Some(
+            HeaderValue::from_str(&link_header("/boats", &raw_query, &links))
+                .map_err(|_| reject::custom(Error::InvalidParameter))?,
+        )
+    };
+    // cursors hold stored values, so the page is only converted once they are made
+    let boats: Vec<Boat> = boats.into_iter().map(|boat| boat.in_units(units)).collect();
+    listing_page(&boats, total, links, &conditions, last_modified)
+        .map(|response| label_units(response, units))
+        .map_err(reject::custom)
+}
+
+// relation names and cursors of the pages next to a page
+type PageLinks = Vec<(&'static str, String)>;
+
+// a page of a sorted listing along with the cursors of the pages next to it, which are only
+// handed out to listings that aren't paged by offset
+fn sorted_page(
+    conn: &mut SqliteConnection,
+    query: &BoatQuery,
+    sort: BoatSort,
+    cursor: Option<&BoatCursor>,
+    limit: u32,
+    now: DateTime<Utc>,
+) -> Result<(Vec<Boat>, PageLinks), Error> {
+    let before = cursor.is_some_and(|cursor| cursor.before);
+    let mut boats = filter_boats(query);
+    if let Some(cursor) = cursor {
+        boats = boats.filter(seek_boats(cursor)?);
+    }
+    // pages before a cursor are fetched in reverse order and flipped back afterwards
+    let scan = if before {
+        BoatSort {
+            field: sort.field,
+            direction: sort.direction.reversed(),
+        }
+    } else {
+        sort
+    };
+    // fetch one extra row to find out whether another page follows
+    let mut boats: Vec<Boat> = sort_boats(boats, scan)
+        .select((boats::all_columns, in_maintenance(now)))
+        .limit(i64::from(limit) + 1)
+        .offset(query.offset.unwrap_or(0).into())
+        .load(conn)?
+        .into_iter()
+        .map(|(boat, in_maintenance)| available(boat, in_maintenance))
+        .collect();
+    let has_more = boats.len() > limit as usize;
+    boats.truncate(limit as usize);
+    if before {
+        boats.reverse();
+    }
+
+    let (has_next, has_prev) = match cursor {
+        Some(_) if before => (true, has_more),
+        Some(_) => (has_more, true),
+        None => (has_more, false),
+    };
+    let mut links = Vec::new();
+    if query.offset.is_none() {
+        if let (true, Some(last)) = (has_next, boats.last()) {
+            links.push(("next", boat_cursor(last, sort, false)));
+        }
+        if let (true, Some(first)) = (has_prev, boats.first()) {
+            links.push(("prev", boat_cursor(first, sort, true)));
+        }
+    }
+    Ok((boats, links))
+}
+
+// a page of a listing, tagged with a hash of its content so that clients can revalidate it
+fn listing_page(
+    items: &impl Serialize,
+    total: i64,
+    links: Option<HeaderValue>,
+    conditions: &Conditions,
+    last_modified: Option<DateTime<Utc>>,
+) -> Result<Response, Error> {
+    let body = serde_json::to_vec(items).map_err(|_| Error::InvalidParameter)?;
+    let etag = content_etag(&[&body, &total.to_be_bytes()]);
+    let mut response = Response::new(Body::from(body));
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    if let Some(links) = links {
+        headers.insert(LINK, links);
+    }
+    Ok(cached(response, conditions, &etag, last_modified))
+}
+
+// streams the boats matching a listing query straight from the database
+fn export_boats(
+    query: BoatQuery,
+    sort: BoatSort,
+    format: ExportFormat,
+    units: Units,
+    total: i64,
+    mut conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+) -> Response {
+    let download = query.download == Some(true);
+    let (sender, receiver) = mpsc::channel::<Result<Bytes, std::io::Error>>(EXPORT_CHANNEL_SIZE);
+    tokio::task::spawn_blocking(move || {
+        let mut writer = RowWriter::new(format);
+        let now = Utc::now();
+        let result = match query.near {
+            Some(near) => filter_boats(&query)
+                .select((boats::all_columns, distance_km(near), in_maintenance(now)))
+                .order((distance_km(near).asc(), boats::id.asc()))
+                .load_iter::<(Boat, Option<f64>, bool), DefaultLoadingMode>(&mut conn)
+                .map_err(Error::from)
+                .and_then(|rows| {
+                    let rows = rows.map(|row| {
+                        row.map(|(boat, distance_km, in_maintenance)| BoatListing {
+                            boat: available(boat, in_maintenance).in_units(units),
+                            distance_km,
+                        })
+                    });
+                    send_rows(rows, &mut writer, &sender)
+                }),
+            None => sort_boats(filter_boats(&query), sort)
+                .select((boats::all_columns, in_maintenance(now)))
+                .load_iter::<(Boat, bool), DefaultLoadingMode>(&mut conn)
+                .map_err(Error::from)
+                .and_then(|rows| {
+                    let rows = rows.map(|row| {
+                        row.map(|(boat, in_maintenance)| {
+                            available(boat, in_maintenance).in_units(units)
+                        })
+                    });
+                    send_rows(rows, &mut writer, &sender)
+                }),
+        };
+        match result {
+            Ok(()) => {
+                let mut buffer = Vec::new();
+                writer.finish(&mut buffer);
+                let _ = sender.blocking_send(Ok(Bytes::from(buffer)));
+            }
+            // the client only notices a failed export by the connection being cut short
+            Err(e) => {
+                error!("Export failed: {}", e);
+                let _ = sender.blocking_send(Err(std::io::Error::other(e.to_string())));
+            }
+        }
+    });
+
+    let body = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+    let mut response = Response::new(Body::wrap_stream(body));
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    if download {
+        let disposition = format!("attachment; filename=\"boats.{}\"", format.extension());
+        if let Ok(disposition) = HeaderValue::from_str(&disposition) {
+            headers.insert(CONTENT_DISPOSITION, disposition);
+        }
+    }
+    response
+}
+
+// writes rows in chunks of about `EXPORT_CHUNK_SIZE` bytes, stops early if the client is gone
+fn send_rows<T: Serialize>(
+    rows: impl Iterator<Item = diesel::QueryResult<T>>,
+    writer: &mut RowWriter,
+    sender: &mpsc::Sender<Result<Bytes, std::io::Error>>,
+) -> Result<(), Error> {
+    let mut buffer = Vec::new();
+    for row in rows {
+        writer.row(&row?, &mut buffer)?;
+        if buffer.len() >= EXPORT_CHUNK_SIZE
+            && sender
+                .blocking_send(Ok(Bytes::from(std::mem::take(&mut buffer))))
+                .is_err()
+        {
+            return Ok(());
+        }
+    }
+    if !buffer.is_empty() {
+        let _ = sender.blocking_send(Ok(Bytes::from(buffer)));
+    }
+    Ok(())
+}
+
+pub async fn search_boats(
+    query: BoatSearchQuery,
+    units: Units,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let fts_query = fts_query(&query.q).map_err(reject::custom)?;
+
+    let mut conn = acquire_connection(&pool).await?;
+    let total: BoatSearchCount = diesel::sql_query(
+        "SELECT COUNT(*) AS count \
+             FROM boats_fts JOIN boats ON boats.id = boats_fts.rowid \
+             WHERE boats_fts MATCH ? AND boats.deleted_at IS NULL",
+    )
+    .bind::<Text, _>(&fts_query)
+    .get_result(&mut conn)
+    .map_err(|e| error!("{}", e))
+    .map_err(|_| reject::custom(Error::InvalidParameter))?;
+    // name matches weigh twice as much as make or model matches
+    let boats: Vec<BoatSearchResult> = diesel::sql_query(
+        "SELECT boats.*, \
+            CASE WHEN ? THEN snippet(boats_fts, -1, ?, ?, '...', 16) END AS snippet \
+         FROM boats_fts JOIN boats ON boats.id = boats_fts.rowid \
+         WHERE boats_fts MATCH ? AND boats.deleted_at IS NULL \
+         ORDER BY bm25(boats_fts, 2.0, 1.0, 1.0), boats.id \
+         LIMIT ? OFFSET ?",
+    )
+    .bind::<Bool, _>(query.highlight.unwrap_or(false))
+    .bind::<Text, _>(HIGHLIGHT_START)
+    .bind::<Text, _>(HIGHLIGHT_END)
+    .bind::<Text, _>(&fts_query)
+    .bind::<BigInt, _>(i64::from(limit))
+    .bind::<BigInt, _>(i64::from(query.offset.unwrap_or(0)))
+    .load(&mut conn)
+    .map_err(|e| error!("{}", e))
+    .map_err(|_| reject::custom(Error::InvalidParameter))?;
+
+    let boats: Vec<BoatSearchResult> = boats
+        .into_iter()
+        .map(|result| BoatSearchResult {
+            boat: result.boat.in_units(units),
+            ..result
+        })
+        .collect();
+    Ok(label_units(
+        reply::with_header(
+            reply::json(&boats),
+            TOTAL_COUNT_HEADER,
+            total.count.to_string(),
+        ),
+        units,
+    ))
+}
+
+fn boat_cursor(boat: &Boat, sort: BoatSort, before: bool) -> String {
+    encode_cursor(&BoatCursor {
+        sort,
+        key: boat.sort_key(sort.field),
+        id: boat.id,
+        before,
+    })
+}
+
+fn filter_boats(query: &BoatQuery) -> boats::BoxedQuery<'static, Sqlite> {
+    let mut boats = boats::table
+        .filter(boats::deleted_at.is_null())
+        .into_boxed();
+    if let Some(make) = &query.make {
+        boats = boats.filter(boats::make.eq(make.clone()));
+    }
+    if let Some(model) = &query.model {
+        boats = boats.filter(boats::model.eq(model.clone()));
+    }
+    if let Some(year_min) = query.year_min {
+        boats = boats.filter(boats::year.ge(year_min));
+    }
+    if let Some(year_max) = query.year_max {
+        boats = boats.filter(boats::year.le(year_max));
+    }
+    if let Some(length_min) = query.length_min {
+        boats = boats.filter(boats::length.ge(length_min));
+    }
+    if let Some(length_max) = query.length_max {
+        boats = boats.filter(boats::length.le(length_max));
+    }
+    if let Some(beam_max) = query.beam_max {
+        boats = boats.filter(boats::beam.le(beam_max));
+    }
+    if let Some(hull_type) = &query.hull_type {
+        boats = boats.filter(boats::hull_type.eq(hull_type.clone()));
+    }
+    if let Some(hull_material) = &query.hull_material {
+        boats = boats.filter(boats::hull_material.eq(hull_material.clone()));
+    }
+    if let Some(propulsion) = &query.propulsion {
+        boats = boats.filter(boats::propulsion.eq(propulsion.clone()));
+    }
+    if let Some(fuel_type) = &query.fuel_type {
+        boats = boats.filter(boats::fuel_type.eq(fuel_type.clone()));
+    }
+    if let Some(engine_count_min) = query.engine_count_min {
+        boats = boats.filter(boats::engine_count.ge(engine_count_min));
+    }
+    if let Some(engine_power_min) = query.engine_power_min {
+        boats = boats.filter(boats::engine_power.ge(engine_power_min));
+    }
+    if let Some(berths_min) = query.berths_min {
+        boats = boats.filter(boats::berths.ge(berths_min));
+    }
+    if let Some(cabins_min) = query.cabins_min {
+        boats = boats.filter(boats::cabins.ge(cabins_min));
+    }
+    if let Some(passengers_min) = query.passengers_min {
+        boats = boats.filter(boats::max_passengers.ge(passengers_min));
+    }
+    if let Some(draft_max) = query.draft_max {
+        boats = boats.filter(boats::draft.le(draft_max));
+    }
+    if let Some(min_rating) = query.min_rating {
+        boats = boats.filter(boats::rating.ge(min_rating));
+    }
+    let tags = query.tags();
+    if !tags.is_empty() {
+        let tagged = |names: Vec<String>| {
+            exists(
+                boat_tags::table
+                    .inner_join(tags::table)
+                    .filter(boat_tags::boat_id.eq(boats::id))
+                    .filter(tags::name.eq_any(names)),
+            )
+        };
+        match query.tag_match.unwrap_or_default() {
+            TagMatch::Any => boats = boats.filter(tagged(tags)),
+            TagMatch::All => {
+                for tag in tags {
+                    boats = boats.filter(tagged(vec![tag]));
+                }
+            }
+        }
+    }
+    let now = Utc::now();
+    match query.is_available {
+        Some(1) => {
+            boats = boats
+                .filter(boats::is_available.eq(1))
+                .filter(not(in_maintenance(now)))
+        }
+        Some(0) => boats = boats.filter(boats::is_available.eq(0).or(in_maintenance(now))),
+        Some(is_available) => boats = boats.filter(boats::is_available.eq(is_available)),
+        None => {}
+    }
+    if let (Some(from), Some(to)) = (query.available_from, query.available_to) {
+        boats = boats
+            .filter(boats::is_available.eq(1))
+            .filter(not(exists(
+                bookings::table
+                    .filter(bookings::boat_id.eq(boats::id))
+                    .filter(bookings::starts_at.lt(to))
+                    .filter(bookings::ends_at.gt(from)),
+            )))
+            .filter(not(exists(
+                blackouts::table
+                    .filter(blackouts::boat_id.eq(boats::id))
+                    .filter(blackouts::starts_at.lt(to))
+                    .filter(blackouts::ends_at.gt(from)),
+            )))
+            .filter(not(exists(
+                maintenance_windows::table
+                    .filter(maintenance_windows::boat_id.eq(boats::id))
+                    .filter(maintenance_windows::starts_at.lt(to))
+                    .filter(maintenance_windows::ends_at.gt(from)),
+            )));
+    }
+    if let Some(near) = query.near {
+        let radius = query.radius_km.unwrap_or(DEFAULT_RADIUS_KM);
+        boats = boats.filter(
+            boats::marina_id.eq_any(
+                marinas::table
+                    .filter(
+                        haversine_km(
+                            marinas::latitude,
+                            marinas::longitude,
+                            near.latitude,
+                            near.longitude,
+                        )
+                        .le(radius),
+                    )
+                    .select(marinas::id.nullable()),
+            ),
+        );
+    }
+    if let Some(bbox) = query.bbox {
+        let marinas = marinas::table
+            .filter(marinas::latitude.between(bbox.min_latitude, bbox.max_latitude))
+            .into_boxed();
+        let marinas = if bbox.min_longitude <= bbox.max_longitude {
+            marinas.filter(marinas::longitude.between(bbox.min_longitude, bbox.max_longitude))
+        } else {
+            marinas.filter(
+                marinas::longitude
+                    .ge(bbox.min_longitude)
+                    .or(marinas::longitude.le(bbox.max_longitude)),
+            )
+        };
+        boats = boats.filter(boats::marina_id.eq_any(marinas.select(marinas::id.nullable())));
+    }
+    boats
+}
+
+type BoatCondition = Box<dyn BoxableExpression<boats::table, Sqlite, SqlType = Bool>>;
+
+// whether the boat is in a maintenance window at `now`
+fn in_maintenance(now: DateTime<Utc>) -> BoatCondition {
+    Box::new(exists(
+        maintenance_windows::table
+            .filter(maintenance_windows::boat_id.eq(boats::id))
+            .filter(maintenance_windows::starts_at.le(now))
+            .filter(maintenance_windows::ends_at.gt(now)),
+    ))
+}
+
+// boats are unavailable while they are in a maintenance window, whatever their flag says
+fn available(boat: Boat, in_maintenance: bool) -> Boat {
+    if in_maintenance {
+        Boat {
+            is_available: 0,
+            ..boat
+        }
+    } else {
+        boat
+    }
+}
+
+// distance from a point to the boat's home marina, NULL for boats without one
+fn distance_km(
+    near: GeoPoint,
+) -> Box<dyn BoxableExpression<boats::table, Sqlite, SqlType = Nullable<Double>>> {
+    Box::new(
+        marinas::table
+            .filter(marinas::id.nullable().eq(boats::marina_id))
+            .select(haversine_km(
+                marinas::latitude,
+                marinas::longitude,
+                near.latitude,
+                near.longitude,
+            ))
+            .single_value(),
+    )
+}
+
+fn sort_boats(
+    boats: boats::BoxedQuery<'static, Sqlite>,
+    sort: BoatSort,
+) -> boats::BoxedQuery<'static, Sqlite> {
+    // id is always the tie-breaker so that pages are stable
+    match (sort.field, sort.direction) {
+        (SortField::Id, SortDirection::Asc) => boats.order(boats::id.asc()),
+        (SortField::Id, SortDirection::Desc) => boats.order(boats::id.desc()),
+        (SortField::Name, SortDirection::Asc) => boats.order((boats::name.asc(), boats::id.asc())),
+        (SortField::Name, SortDirection::Desc) => {
+            boats.order((boats::name.desc(), boats::id.desc()))
+        }
+        (SortField::Make, SortDirection::Asc) => boats.order((boats::make.asc(), boats::id.asc())),
+        (SortField::Make, SortDirection::Desc) => {
+            boats.order((boats::make.desc(), boats::id.desc()))
+        }
+        (SortField::Model, SortDirection::Asc) => {
+            boats.order((boats::model.asc(), boats::id.asc()))
+        }
+        (SortField::Model, SortDirection::Desc) => {
+            boats.order((boats::model.desc(), boats::id.desc()))
+        }
+        (SortField::Year, SortDirection::Asc) => boats.order((boats::year.asc(), boats::id.asc())),
+        (SortField::Year, SortDirection::Desc) => {
+            boats.order((boats::year.desc(), boats::id.desc()))
+        }
+        (SortField::Length, SortDirection::Asc) => {
+            boats.order((boats::length.asc(), boats::id.asc()))
+        }
+        (SortField::Length, SortDirection::Desc) => {
+            boats.order((boats::length.desc(), boats::id.desc()))
+        }
+        (SortField::Beam, SortDirection::Asc) => boats.order((boats::beam.asc(), boats::id.asc())),
+        (SortField::Beam, SortDirection::Desc) => {
+            boats.order((boats::beam.desc(), boats::id.desc()))
+        }
+        (SortField::Rating, SortDirection::Asc) => {
+            boats.order((boats::rating.asc(), boats::id.asc()))
+        }
+        (SortField::Rating, SortDirection::Desc) => {
+            boats.order((boats::rating.desc(), boats::id.desc()))
+        }
+    }
+}
+
+type BoatPredicate = Box<dyn BoxableExpression<boats::table, Sqlite, SqlType = Nullable<Bool>>>;
+
+// keyset condition selecting the rows that follow `(column, id)` in scan order
+macro_rules! seek {
+    ($column:expr, $value:expr, $id:expr, $ascending:expr) => {{
+        let value = $value;
+        if $ascending {
+            Box::new(
+                $column
+                    .gt(value.clone())
+                    .or($column.eq(value).and(boats::id.gt($id)))
+                    .nullable(),
+            ) as BoatPredicate
+        } else {
+            Box::new(
+                $column
+                    .lt(value.clone())
+                    .or($column.eq(value).and(boats::id.lt($id)))
+                    .nullable(),
+            ) as BoatPredicate
+        }
+    }};
+}
+
+// same as `seek!` for nullable columns, where SQLite orders NULLs before any value
+macro_rules! seek_nullable {
+    ($column:expr, $value:expr, $id:expr, $ascending:expr) => {
+        match ($value, $ascending) {
+            (Some(value), true) => Box::new(
+                $column
+                    .gt(value)
+                    .or($column.eq(value).and(boats::id.gt($id))),
+            ) as BoatPredicate,
+            (Some(value), false) => Box::new(
+                $column
+                    .lt(value)
+                    .or($column.eq(value).and(boats::id.lt($id)))
+                    .or($column.is_null()),
+            ) as BoatPredicate,
+            (None, true) => Box::new(
+                $column
+                    .is_null()
+                    .and(boats::id.gt($id))
+                    .or($column.is_not_null())
+                    .nullable(),
+            ) as BoatPredicate,
+            (None, false) => {
+                Box::new($column.is_null().and(boats::id.lt($id)).nullable()) as BoatPredicate
+            }
+        }
+    };
+}
+
+fn seek_boats(cursor: &BoatCursor) -> Result<BoatPredicate, Error> {
+    let ascending = (cursor.sort.direction == SortDirection::Asc) != cursor.before;
+    let id = cursor.id;
+    Ok(match (cursor.sort.field, &cursor.key) {
+        (SortField::Id, SortKey::Integer(_)) => seek!(boats::id, id, id, ascending),
+        (SortField::Name, SortKey::Text(value)) => seek!(boats::name, value.clone(), id, ascending),
+        (SortField::Make, SortKey::Text(value)) => seek!(boats::make, value.clone(), id, ascending),
+        (SortField::Model, SortKey::Text(value)) => {
+            seek!(boats::model, value.clone(), id, ascending)
+        }
+        (SortField::Year, SortKey::Integer(value)) => seek!(boats::year, *value, id, ascending),
+        (SortField::Length, SortKey::Float(value)) => {
+            seek_nullable!(boats::length, *value, id, ascending)
+        }
+        (SortField::Beam, SortKey::Float(value)) => {
+            seek_nullable!(boats::beam, *value, id, ascending)
+        }
+        (SortField::Rating, SortKey::Double(value)) => {
+            seek_nullable!(boats::rating, *value, id, ascending)
+        }
+        _ => return Err(Error::InvalidParameter),
+    })
 }
 
 pub async fn create_boat(
+    units: Units,
     boat: NewBoat,
+    user: User,
     pool: SharedConnectionPool,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut conn = acquire_connection(&pool).await?;
-    diesel::insert_into(boats::table)
-        .values(&boat)
-        .execute(&mut conn)
-        .map_err(|_| reject::custom(Error::NotFound))?;
+    let boat = insert_boat(&mut conn, boat.into_metric(units), &user).map_err(reject::custom)?;
+    let location = format!("/boats/{}", boat.id);
+    let version = boat.version;
     Ok(reply::with_status(
-        reply::json(&String::from("Boat created")),
+        reply::with_header(
+            label_units(
+                with_etag(reply::json(&boat.in_units(units)), version, units),
+                units,
+            ),
+            LOCATION,
+            location,
+        ),
         StatusCode::CREATED,
     ))
 }
 
+// PUT replaces every editable field, optional fields left out are cleared
 pub async fn update_boat(
     id: i32,
-    boat: UpdateBoat,
+    if_match: Option<String>,
+    units: Units,
+    boat: NewBoat,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let boat = conn
+        .immediate_transaction(|conn| {
+            replace_boat(conn, id, if_match.as_deref(), &user, |_| {
+                Ok(boat.into_metric(units))
+            })
+        })
+        .map_err(reject::custom)?;
+    let version = boat.version;
+    Ok(label_units(
+        with_etag(reply::json(&boat.in_units(units)), version, units),
+        units,
+    ))
+}
+
+// PATCH applies a JSON merge patch or JSON patch to the editable fields of the boat
+pub async fn patch_boat(
+    id: i32,
+    content_type: Option<String>,
+    if_match: Option<String>,
+    units: Units,
+    body: Bytes,
+    user: User,
     pool: SharedConnectionPool,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let content_type = content_type
+        .as_deref()
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase());
+    let merge = match content_type.as_deref() {
+        Some(MERGE_PATCH) => true,
+        Some(JSON_PATCH) => false,
+        _ => return Err(reject::custom(Error::UnsupportedMediaType)),
+    };
+    let patch: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|_| reject::custom(Error::InvalidParameter))?;
+
     let mut conn = acquire_connection(&pool).await?;
-    diesel::update(boats::table.filter(boats::id.eq(id)))
+    let boat = conn
+        .immediate_transaction(|conn| {
+            replace_boat(conn, id, if_match.as_deref(), &user, |current| {
+                patched_boat(current, &patch, merge, units)
+            })
+        })
+        .map_err(reject::custom)?;
+    let version = boat.version;
+    Ok(label_units(
+        with_etag(reply::json(&boat.in_units(units)), version, units),
+        units,
+    ))
+}
+
+// applies a patch to the boat as the client sees it in `units`
+fn patched_boat(
+    current: &Boat,
+    patch: &serde_json::Value,
+    merge: bool,
+    units: Units,
+) -> Result<NewBoat, Error> {
+    let mut document = serde_json::to_value(BoatState::from(&current.clone().in_units(units)))
+        .map_err(|_| Error::InvalidParameter)?;
+    let editable: Vec<String> = document
+        .as_object()
+        .map(|fields| fields.keys().cloned().collect())
+        .unwrap_or_default();
+    if merge {
+        merge_patch(&mut document, patch);
+    } else {
+        json_patch(&mut document, patch)?;
+    }
+    // a patch may add fields that can't be edited or remove required ones, which are
+    // validation errors rather than a malformed request
+    let mut validator = Validator::default();
+    if let Some(fields) = document.as_object() {
+        for field in fields.keys().filter(|field| !editable.contains(field)) {
+            validator.error(
+                field.clone(),
+                INVALID_VALUE,
+                format!("{} is not an editable field", field),
+            );
+        }
+    }
+    for field in REQUIRED_FIELDS {
+        if document.get(field).is_none_or(serde_json::Value::is_null) {
+            validator.error(field, REQUIRED, format!("{} is required", field));
+        }
+    }
+    validator.finish()?;
+    serde_json::from_value::<NewBoat>(document)
+        .map(|boat| boat.into_metric_from(units, current))
+        .map_err(|_| Error::InvalidParameter)
+}
+
+// validates a new boat and inserts it on behalf of `user`. A taken name is a conflict, an
+// unknown marina an invalid reference
+pub fn insert_boat(
+    conn: &mut SqliteConnection,
+    mut boat: NewBoat,
+    user: &User,
+) -> Result<Boat, Error> {
+    boat.validate()?;
+    let boat = NewBoat {
+        owner_email: Some(user.email.clone()),
+        ..boat
+    };
+    let now = Utc::now();
+    Ok(diesel::insert_into(boats::table)
+        .values((&boat, boats::created_at.eq(now), boats::updated_at.eq(now)))
+        .returning(Boat::as_returning())
+        .get_result(conn)?)
+}
+
+// replaces the editable fields of a boat with the validated result of `change`, to be run in
+// a transaction
+pub fn replace_boat(
+    conn: &mut SqliteConnection,
+    id: i32,
+    if_match: Option<&str>,
+    user: &User,
+    change: impl FnOnce(&Boat) -> Result<NewBoat, Error>,
+) -> Result<Boat, Error> {
+    authorize_boat_owner(conn, id, user)?;
+    let current: Boat = boats::table
+        .find(id)
+        .select(Boat::as_select())
+        .first(conn)?;
+    check_if_match(if_match, current.version)?;
+    let mut boat = change(&current)?;
+    boat.validate()?;
+    record_revision(conn, &current, RevisionAction::Update, user)?;
+    Ok(diesel::update(boats::table.find(id))
         .set((
-            boat.name.map(|v| boats::name.eq(v)),
-            boat.make.map(|v| boats::make.eq(v)),
-            boat.model.map(|v| boats::model.eq(v)),
-            boat.year.map(|v| boats::year.eq(v)),
-            boat.length.map(|v| boats::length.eq(v)),
-            boat.beam.map(|v| boats::beam.eq(v)),
-            boat.is_available.map(|v| boats::is_available.eq(v)),
+            BoatState::from(boat),
+            boats::version.eq(boats::version + 1),
+            boats::updated_at.eq(Utc::now()),
         ))
-        .execute(&mut conn)
-        .map_err(|_| reject::custom(Error::NotFound))?;
-    Ok(reply::json(&format!("Boat {} updated", id)))
+        .returning(Boat::as_returning())
+        .get_result(conn)?)
+}
+
+// moves a boat to the trash, to be run in a transaction
+pub fn trash_boat(
+    conn: &mut SqliteConnection,
+    id: i32,
+    if_match: Option<&str>,
+    user: &User,
+) -> Result<(), Error> {
+    authorize_boat_owner(conn, id, user)?;
+    let current: Boat = boats::table
+        .find(id)
+        .select(Boat::as_select())
+        .first(conn)?;
+    check_if_match(if_match, current.version)?;
+    record_revision(conn, &current, RevisionAction::Delete, user)?;
+    let now = Utc::now();
+    diesel::update(boats::table.find(id))
+        .set((
+            boats::deleted_at.eq(now),
+            boats::version.eq(boats::version + 1),
+            boats::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+    Ok(())
 }
 
 pub async fn delete_boat(
     id: i32,
+    if_match: Option<String>,
+    user: User,
     pool: SharedConnectionPool,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let mut conn = acquire_connection(&pool).await?;
-    diesel::delete(boats::table.filter(boats::id.eq(id)))
-        .execute(&mut conn)
-        .map_err(|_| reject::custom(Error::NotFound))?;
+    // boats are only moved to the trash, they are removed for good by `purge_trash`
+    conn.immediate_transaction(|conn| trash_boat(conn, id, if_match.as_deref(), &user))
+        .map_err(reject::custom)?;
     Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
 }
+
+pub async fn get_trash(
+    units: Units,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    // admins see every deleted boat, everyone else only their own
+    let mut boats = boats::table
+        .filter(boats::deleted_at.is_not_null())
+        .into_boxed();
+    if !user.is_admin {
+        boats = boats.filter(boats::owner_email.eq(user.email));
+    }
+    let boats: Vec<Boat> = boats
+        .order((boats::deleted_at.desc(), boats::id.desc()))
+        .select(Boat::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    let boats: Vec<Boat> = boats.into_iter().map(|boat| boat.in_units(units)).collect();
+    Ok(label_units(reply::json(&boats), units))
+}
+
+pub async fn restore_boat(
+    id: i32,
+    units: Units,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let boat = conn
+        .immediate_transaction::<Boat, Error, _>(|conn| {
+            let current: Boat = boats::table
+                .find(id)
+                .filter(boats::deleted_at.is_not_null())
+                .select(Boat::as_select())
+                .first(conn)?;
+            check_owner(current.owner_email.as_ref(), &user)?;
+            record_revision(conn, &current, RevisionAction::Restore, &user)?;
+            Ok(diesel::update(boats::table.find(id))
+                .set((
+                    boats::deleted_at.eq(None::<DateTime<Utc>>),
+                    boats::version.eq(boats::version + 1),
+                    boats::updated_at.eq(Utc::now()),
+                ))
+                .returning(Boat::as_returning())
+                .get_result(conn)?)
+        })
+        .map_err(reject::custom)?;
+    let version = boat.version;
+    Ok(label_units(
+        with_etag(reply::json(&boat.in_units(units)), version, units),
+        units,
+    ))
+}
+
+pub async fn purge_trash(
+    user: User,
+    storage: SharedStorage,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !user.is_admin {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    let retention_days = get_config()
+        .get_int("trash.retention_days")
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+    let cutoff = Utc::now() - Duration::days(retention_days);
+
+    let mut conn = acquire_connection(&pool).await?;
+    let (purged, media_keys) = conn
+        .immediate_transaction::<(Vec<i32>, Vec<(String, Option<String>)>), Error, _>(|conn| {
+            let boats: Vec<Boat> = boats::table
+                .filter(boats::deleted_at.lt(cutoff))
+                .select(Boat::as_select())
+                .load(conn)?;
+            let ids: Vec<i32> = boats.iter().map(|boat| boat.id).collect();
+            for boat in &boats {
+                record_revision(conn, boat, RevisionAction::Purge, &user)?;
+            }
+            // media rows go with the boats, their files have to be removed separately
+            let media_keys = boat_media::table
+                .filter(boat_media::boat_id.eq_any(&ids))
+                .select((boat_media::storage_key, boat_media::thumbnail_key))
+                .load(conn)?;
+            diesel::delete(boats::table.filter(boats::id.eq_any(&ids))).execute(conn)?;
+            Ok((ids, media_keys))
+        })
+        .map_err(reject::custom)?;
+    let keys = media_keys
+        .into_iter()
+        .flat_map(|(key, thumbnail_key)| [Some(key), thumbnail_key])
+        .collect();
+    handlers::media::remove(storage, keys).await;
+    Ok(reply::json(&PurgeResponse { purged }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_connection, test_user};
+    use diesel::sql_types::Integer;
+    use serde_json::json;
+
+    fn new_boat(name: &str) -> NewBoat {
+        serde_json::from_value(json!({
+            "name": name,
+            "make": "Beneteau",
+            "model": "Oceanis",
+            "year": 2015,
+        }))
+        .unwrap()
+    }
+
+    // stores a rating the way reviews do, as an average rounded in SQL
+    fn rate(conn: &mut SqliteConnection, id: i32, total: i32, count: i32) {
+        diesel::sql_query("UPDATE boats SET rating = round(CAST(? AS REAL) / ?, 2) WHERE id = ?")
+            .bind::<Integer, _>(total)
+            .bind::<Integer, _>(count)
+            .bind::<Integer, _>(id)
+            .execute(conn)
+            .unwrap();
+    }
+
+    fn query(value: serde_json::Value) -> BoatQuery {
+        serde_json::from_value(value).unwrap()
+    }
+
+    // ids on each page of a listing, following the `rel` links from `cursor` to the end
+    fn walk(
+        conn: &mut SqliteConnection,
+        query: &BoatQuery,
+        sort: BoatSort,
+        limit: u32,
+        mut cursor: Option<BoatCursor>,
+        rel: &str,
+    ) -> Vec<Vec<i32>> {
+        let mut pages = Vec::new();
+        loop {
+            let (page, links) =
+                sorted_page(conn, query, sort, cursor.as_ref(), limit, Utc::now()).unwrap();
+            pages.push(page.iter().map(|boat| boat.id).collect());
+            let Some((_, link)) = links.into_iter().find(|(name, _)| *name == rel) else {
+                return pages;
+            };
+            assert!(pages.len() < 10, "paging does not advance");
+            cursor = Some(decode_cursor(&link).unwrap());
+        }
+    }
+
+    fn pages(conn: &mut SqliteConnection, query: &BoatQuery, sort: BoatSort) -> Vec<Vec<i32>> {
+        walk(conn, query, sort, 1, None, "next")
+    }
+
+    fn rated_boats(conn: &mut SqliteConnection) -> Vec<i32> {
+        let user = test_user(conn, "owner@example.com", false);
+        let ids: Vec<i32> = ["Aurora", "Bora", "Calypso", "Dorado", "Eos"]
+            .into_iter()
+            .map(|name| insert_boat(conn, new_boat(name), &user).unwrap().id)
+            .collect();
+        for &id in &ids[..3] {
+            rate(conn, id, 13, 3);
+        }
+        rate(conn, ids[3], 5, 1);
+        ids
+    }
+
+    #[test]
+    fn pages_forward_and_back_through_ties() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let ids: Vec<i32> = [
+            ("Aurora", "Bavaria"),
+            ("Bora", "Amel"),
+            ("Calypso", "Bavaria"),
+            ("Dorado", "Amel"),
+            ("Eos", "Contest"),
+        ]
+        .into_iter()
+        .map(|(name, make)| {
+            let boat = NewBoat {
+                make: make.to_owned(),
+                ..new_boat(name)
+            };
+            insert_boat(&mut conn, boat, &user).unwrap().id
+        })
+        .collect();
+        let all = query(json!({}));
+        let by_make = BoatSort {
+            field: SortField::Make,
+            direction: SortDirection::Asc,
+        };
+
+        let forward = walk(&mut conn, &all, by_make, 2, None, "next");
+        assert_eq!(
+            forward,
+            [vec![ids[1], ids[3]], vec![ids[0], ids[2]], vec![ids[4]]]
+        );
+        // going back from the last page gives the same pages in reverse
+        let last = boats::table
+            .find(ids[4])
+            .select(Boat::as_select())
+            .first(&mut conn)
+            .unwrap();
+        let cursor = decode_cursor(&boat_cursor(&last, by_make, true)).unwrap();
+        let back = walk(&mut conn, &all, by_make, 2, Some(cursor), "prev");
+        assert_eq!(back, [vec![ids[0], ids[2]], vec![ids[1], ids[3]]]);
+
+        // only pages after the first one link back, and offsets get no links at all
+        let (_, links) = sorted_page(&mut conn, &all, by_make, None, 2, Utc::now()).unwrap();
+        assert_eq!(
+            links.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            ["next"]
+        );
+        let offset = query(json!({ "offset": 2 }));
+        let (page, links) = sorted_page(&mut conn, &offset, by_make, None, 2, Utc::now()).unwrap();
+        assert_eq!(
+            page.iter().map(|boat| boat.id).collect::<Vec<_>>(),
+            [ids[0], ids[2]]
+        );
+        assert!(links.is_empty());
+
+        // cursors only cover the boats matching the filters
+        let bavaria = query(json!({ "make": "Bavaria" }));
+        let pages = walk(&mut conn, &bavaria, by_make, 1, None, "next");
+        assert_eq!(pages, [vec![ids[0]], vec![ids[2]]]);
+    }
+
+    #[test]
+    fn pages_through_tied_ratings() {
+        let mut conn = test_connection();
+        let ids = rated_boats(&mut conn);
+        let all = query(json!({}));
+
+        let ascending = BoatSort {
+            field: SortField::Rating,
+            direction: SortDirection::Asc,
+        };
+        // boats without a rating come first
+        let expected: Vec<Vec<i32>> = [ids[4], ids[0], ids[1], ids[2], ids[3]]
+            .into_iter()
+            .map(|id| vec![id])
+            .collect();
+        assert_eq!(pages(&mut conn, &all, ascending), expected);
+
+        let descending = BoatSort {
+            field: SortField::Rating,
+            direction: SortDirection::Desc,
+        };
+        let expected: Vec<Vec<i32>> = [ids[3], ids[2], ids[1], ids[0], ids[4]]
+            .into_iter()
+            .map(|id| vec![id])
+            .collect();
+        assert_eq!(pages(&mut conn, &all, descending), expected);
+    }
+
+    #[test]
+    fn imperial_patches_keep_untouched_metric_values() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let mut boat = new_boat("Aurora");
+        boat.length = Some(10.1234);
+        boat.fuel_capacity = Some(123.45);
+        boat.engine_power = Some(55.555);
+        let current = insert_boat(&mut conn, boat, &user).unwrap();
+
+        let patch = json!({ "name": "Borealis", "beam": 11.0 });
+        let patched = patched_boat(&current, &patch, true, Units::Imperial).unwrap();
+        assert_eq!(patched.name, "Borealis");
+        assert_eq!(patched.length, Some(10.1234));
+        assert_eq!(patched.fuel_capacity, Some(123.45));
+        assert_eq!(patched.engine_power, Some(55.555));
+        assert_eq!(patched.beam, Some(3.353));
+
+        // values the patch changes are still converted
+        let patch = json!([{ "op": "replace", "path": "/length", "value": 40.0 }]);
+        let patched = patched_boat(&current, &patch, false, Units::Imperial).unwrap();
+        assert_eq!(patched.length, Some(12.192));
+        assert_eq!(patched.fuel_capacity, Some(123.45));
+    }
+
+    #[test]
+    fn boats_in_maintenance_read_as_unavailable() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let serviced = insert_boat(&mut conn, new_boat("Aurora"), &user).unwrap();
+        let ready = insert_boat(&mut conn, new_boat("Bora"), &user).unwrap();
+        let now = Utc::now();
+        diesel::insert_into(maintenance_windows::table)
+            .values((
+                maintenance_windows::boat_id.eq(serviced.id),
+                maintenance_windows::service_type.eq("antifouling"),
+                maintenance_windows::starts_at.eq(now - Duration::hours(1)),
+                maintenance_windows::ends_at.eq(now + Duration::hours(1)),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+
+        let boats: Vec<(i32, i32)> =
+            sort_boats(filter_boats(&query(json!({}))), BoatSort::default())
+                .select((boats::all_columns, in_maintenance(now)))
+                .load::<(Boat, bool)>(&mut conn)
+                .unwrap()
+                .into_iter()
+                .map(|(boat, in_maintenance)| available(boat, in_maintenance))
+                .map(|boat| (boat.id, boat.is_available))
+                .collect();
+        assert_eq!(boats, [(serviced.id, 0), (ready.id, 1)]);
+
+        // once the window is over the flag counts again
+        let later = now + Duration::hours(2);
+        let in_maintenance: bool = boats::table
+            .find(serviced.id)
+            .select(in_maintenance(later))
+            .first(&mut conn)
+            .unwrap();
+        assert!(!in_maintenance);
+    }
+
+    #[test]
+    fn patches_name_the_fields_they_get_wrong() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let current = insert_boat(&mut conn, new_boat("Aurora"), &user).unwrap();
+
+        let patch = json!({ "colour": "blue", "is_available": null, "make": null });
+        let Err(Error::Validation(errors)) = patched_boat(&current, &patch, true, Units::Metric)
+        else {
+            panic!("the patch should not apply");
+        };
+        let mut fields: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|error| (error.field.as_ref(), error.code))
+            .collect();
+        fields.sort();
+        assert_eq!(
+            fields,
+            [
+                ("colour", INVALID_VALUE),
+                ("is_available", REQUIRED),
+                ("make", REQUIRED)
+            ]
+        );
+
+        // optional fields can still be cleared
+        let patch = json!([{ "op": "remove", "path": "/length" }]);
+        assert!(patched_boat(&current, &patch, false, Units::Metric).is_ok());
+    }
+
+    #[test]
+    fn filters_on_an_exact_rating() {
+        let mut conn = test_connection();
+        let ids = rated_boats(&mut conn);
+        let boats: Vec<i32> = filter_boats(&query(json!({ "min_rating": 4.33 })))
+            .select(boats::id)
+            .order(boats::id.asc())
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(boats, ids[..4]);
+    }
+}
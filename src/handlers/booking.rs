@@ -0,0 +1,137 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, check_owner},
+    models::{
+        booking::{Booking, BookingListing, BookingQuery, NewBooking},
+        user::User,
+    },
+    schema::{blackouts, boats, bookings, maintenance_windows},
+};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use warp::{http::StatusCode, reject, reply};
+
+pub async fn get_bookings(
+    boat_id: i32,
+    query: BookingQuery,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let owner_email: Option<String> = boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::owner_email)
+        .first(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+
+    let mut bookings = bookings::table
+        .filter(bookings::boat_id.eq(boat_id))
+        .into_boxed();
+    if let Some(from) = query.from {
+        bookings = bookings.filter(bookings::ends_at.gt(from));
+    }
+    if let Some(to) = query.to {
+        bookings = bookings.filter(bookings::starts_at.lt(to));
+    }
+    let bookings: Vec<Booking> = bookings
+        .order(bookings::starts_at.asc())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    // who booked is only shown to the boat owner and to the users who made the bookings
+    let is_owner = check_owner(owner_email.as_ref(), &user).is_ok();
+    let bookings: Vec<BookingListing> = bookings
+        .into_iter()
+        .map(|booking| {
+            let show_email = is_owner || booking.user_email == user.email;
+            BookingListing::new(booking, show_email)
+        })
+        .collect();
+    Ok(reply::json(&bookings))
+}
+
+pub async fn create_booking(
+    boat_id: i32,
+    booking: NewBooking,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if booking.starts_at >= booking.ends_at {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let mut conn = acquire_connection(&pool).await?;
+    // immediate transaction takes the write lock up front so that concurrent requests
+    // cannot both pass the overlap check
+    let booking = conn
+        .immediate_transaction::<Booking, Error, _>(|conn| {
+            let is_available: i32 = boats::table
+                .find(boat_id)
+                .filter(boats::deleted_at.is_null())
+                .select(boats::is_available)
+                .first(conn)?;
+            if is_available == 0 {
+                return Err(Error::Conflict);
+            }
+
+            // bookings are half-open intervals, so back-to-back bookings do not overlap
+            let overlapping_bookings: i64 = bookings::table
+                .filter(bookings::boat_id.eq(boat_id))
+                .filter(bookings::starts_at.lt(booking.ends_at))
+                .filter(bookings::ends_at.gt(booking.starts_at))
+                .count()
+                .get_result(conn)?;
+            let overlapping_blackouts: i64 = blackouts::table
+                .filter(blackouts::boat_id.eq(boat_id))
+                .filter(blackouts::starts_at.lt(booking.ends_at))
+                .filter(blackouts::ends_at.gt(booking.starts_at))
+                .count()
+                .get_result(conn)?;
+            let overlapping_maintenance: i64 = maintenance_windows::table
+                .filter(maintenance_windows::boat_id.eq(boat_id))
+                .filter(maintenance_windows::starts_at.lt(booking.ends_at))
+                .filter(maintenance_windows::ends_at.gt(booking.starts_at))
+                .count()
+                .get_result(conn)?;
+            if overlapping_bookings > 0 || overlapping_blackouts > 0 || overlapping_maintenance > 0
+            {
+                return Err(Error::Conflict);
+            }
+
+            Ok(diesel::insert_into(bookings::table)
+                .values((
+                    bookings::boat_id.eq(boat_id),
+                    bookings::user_email.eq(&user.email),
+                    bookings::starts_at.eq(booking.starts_at),
+                    bookings::ends_at.eq(booking.ends_at),
+                ))
+                .returning(Booking::as_returning())
+                .get_result(conn)?)
+        })
+        .map_err(reject::custom)?;
+    Ok(reply::with_status(
+        reply::json(&booking),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn delete_booking(
+    boat_id: i32,
+    booking_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let booking: Booking = bookings::table
+        .filter(bookings::id.eq(booking_id))
+        .filter(bookings::boat_id.eq(boat_id))
+        .first(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    // bookings can only be cancelled by the user who made them
+    if booking.user_email != user.email {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    diesel::delete(bookings::table.find(booking_id))
+        .execute(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
@@ -0,0 +1,133 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, authorize_boat_owner},
+    ical::{parse_events, render_calendar, CalendarEvent, CONTENT_TYPE},
+    models::{
+        blackout::{Blackout, NewBlackout},
+        booking::Booking,
+        maintenance::MaintenanceWindow,
+        user::User,
+    },
+    schema::{blackouts, boats, bookings, maintenance_windows},
+};
+use chrono::{Duration, Utc};
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use std::collections::HashSet;
+use warp::{
+    http::{header::CONTENT_TYPE as CONTENT_TYPE_HEADER, StatusCode},
+    hyper::body::Bytes,
+    reject, reply,
+};
+
+// recurring events are only imported this far into the future
+const RECURRENCE_HORIZON_DAYS: i64 = 730;
+
+pub async fn export_calendar(
+    boat_id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let name: String = boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::name)
+        .first(&mut conn)
+        .map_err(|_| reject::custom(Error::NotFound))?;
+    let bookings: Vec<Booking> = bookings::table
+        .filter(bookings::boat_id.eq(boat_id))
+        .order(bookings::starts_at.asc())
+        .load(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    let blackouts: Vec<Blackout> = blackouts::table
+        .filter(blackouts::boat_id.eq(boat_id))
+        .order(blackouts::starts_at.asc())
+        .load(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    let windows: Vec<MaintenanceWindow> = maintenance_windows::table
+        .filter(maintenance_windows::boat_id.eq(boat_id))
+        .order(maintenance_windows::starts_at.asc())
+        .select(MaintenanceWindow::as_select())
+        .load(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+
+    let events: Vec<CalendarEvent> = bookings
+        .into_iter()
+        .map(|booking| CalendarEvent {
+            uid: format!("booking-{}@boats", booking.id),
+            summary: Some(String::from("Booked")),
+            starts_at: booking.starts_at,
+            ends_at: booking.ends_at,
+        })
+        .chain(blackouts.into_iter().map(|blackout| CalendarEvent {
+            uid: format!("blackout-{}@boats", blackout.id),
+            summary: Some(blackout.summary.unwrap_or_else(|| String::from("Blocked"))),
+            starts_at: blackout.starts_at,
+            ends_at: blackout.ends_at,
+        }))
+        .chain(windows.into_iter().map(|window| CalendarEvent {
+            uid: format!("maintenance-{}@boats", window.id),
+            summary: Some(format!("Maintenance: {}", window.service_type)),
+            starts_at: window.starts_at,
+            ends_at: window.ends_at,
+        }))
+        .collect();
+
+    Ok(reply::with_header(
+        render_calendar(&name, &events),
+        CONTENT_TYPE_HEADER,
+        CONTENT_TYPE,
+    ))
+}
+
+pub async fn import_calendar(
+    boat_id: i32,
+    body: Bytes,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let now = Utc::now();
+    // expanding recurring events can take a while, so it stays off the async workers
+    let events = tokio::task::spawn_blocking(move || {
+        let ics = std::str::from_utf8(&body).map_err(|_| Error::InvalidParameter)?;
+        parse_events(ics, now, now + Duration::days(RECURRENCE_HORIZON_DAYS))
+    })
+    .await
+    .map_err(|_| reject::custom(Error::InvalidParameter))?
+    .map_err(reject::custom)?;
+
+    let mut conn = acquire_connection(&pool).await?;
+    let blackouts = conn
+        .transaction::<Vec<Blackout>, Error, _>(|conn| {
+            authorize_boat_owner(conn, boat_id, &user)?;
+            // re-importing a calendar replaces the periods previously imported for its events
+            let uids: HashSet<&String> = events.iter().map(|event| &event.uid).collect();
+            diesel::delete(
+                blackouts::table
+                    .filter(blackouts::boat_id.eq(boat_id))
+                    .filter(blackouts::uid.eq_any(uids)),
+            )
+            .execute(conn)?;
+            events
+                .iter()
+                .map(|event| {
+                    diesel::insert_into(blackouts::table)
+                        .values(NewBlackout {
+                            boat_id,
+                            uid: event.uid.clone(),
+                            summary: event.summary.clone(),
+                            starts_at: event.starts_at,
+                            ends_at: event.ends_at,
+                        })
+                        .returning(Blackout::as_returning())
+                        .get_result(conn)
+                        .map_err(Error::from)
+                })
+                .collect()
+        })
+        .map_err(reject::custom)?;
+    Ok(reply::with_status(
+        reply::json(&blackouts),
+        StatusCode::CREATED,
+    ))
+}
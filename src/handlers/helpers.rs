@@ -1,20 +1,20 @@
-use crate::{db::SharedConnectionPool, errors::Error};
+use crate::{db::SharedConnectionPool, errors::Error, models::user::User, schema::boats};
 use diesel::{
     r2d2::{ConnectionManager, PooledConnection},
-    SqliteConnection,
+    ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection,
 };
 use warp::{
     http::header::{HeaderMap, HeaderValue, AUTHORIZATION},
     reject,
 };
 
-const BEARER: &'static str = "Bearer ";
+const BEARER: &str = "Bearer ";
 
 pub fn jwt_from_header(headers: &HeaderMap<HeaderValue>) -> Result<String, Error> {
     let auth_header = std::str::from_utf8(
         headers
             .get(AUTHORIZATION)
-            .ok_or_else(|| Error::InvalidAuthHeader)?
+            .ok_or(Error::InvalidAuthHeader)?
             .as_bytes(),
     )
     .map_err(|_| Error::InvalidAuthHeader)?;
@@ -24,9 +24,30 @@ pub fn jwt_from_header(headers: &HeaderMap<HeaderValue>) -> Result<String, Error
 pub async fn acquire_connection(
     pool: &SharedConnectionPool,
 ) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, warp::Rejection> {
-    Ok(pool
-        .lock()
+    pool.lock()
         .await
         .acquire()
-        .map_err(|_| reject::custom(Error::ConnectionFailed))?)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))
+}
+
+// boats and everything attached to them can only be changed by their owner or an admin
+pub fn authorize_boat_owner(
+    conn: &mut SqliteConnection,
+    boat_id: i32,
+    user: &User,
+) -> Result<(), Error> {
+    let owner_email: Option<String> = boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::owner_email)
+        .first(conn)?;
+    check_owner(owner_email.as_ref(), user)
+}
+
+pub fn check_owner(owner_email: Option<&String>, user: &User) -> Result<(), Error> {
+    if user.is_admin || owner_email == Some(&user.email) {
+        Ok(())
+    } else {
+        Err(Error::NoPermission)
+    }
 }
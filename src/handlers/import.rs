@@ -0,0 +1,527 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::{
+        helpers::{acquire_connection, check_owner},
+        revision::record_revision,
+    },
+    models::{
+        boat::{Boat, BoatState, NewBoat},
+        import::{ImportQuery, ImportReport, ImportRow, OnConflict, RowStatus},
+        revision::RevisionAction,
+        user::User,
+    },
+    schema::{boats, marinas},
+    units::Units,
+};
+use chrono::Utc;
+use diesel::{
+    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection,
+};
+use futures_util::{Stream, TryStreamExt};
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, BufReader, Read},
+};
+use tokio::sync::mpsc;
+use warp::{
+    http::StatusCode,
+    hyper::body::{Buf, Bytes},
+    reject, reply,
+};
+
+// valid rows are written in transactions of this many rows. A database failure rolls back
+// its batch and stops the import, the batches written before it are kept and listed in the
+// report
+const BATCH_SIZE: usize = 100;
+const MAX_IMPORT_SIZE: usize = 20 * 1024 * 1024;
+const MAX_IMPORT_ROWS: usize = 10_000;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Csv,
+    Ndjson,
+}
+
+pub async fn import_boats<S, B>(
+    query: ImportQuery,
+    units: Units,
+    content_type: Option<String>,
+    body: S,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection>
+where
+    S: Stream<Item = Result<B, warp::Error>> + Unpin,
+    B: Buf,
+{
+    let format = match content_type
+        .as_deref()
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("text/csv") => Format::Csv,
+        Some("application/x-ndjson") => Format::Ndjson,
+        _ => return Err(reject::custom(Error::UnsupportedMediaType)),
+    };
+    let conn = acquire_connection(&pool).await?;
+
+    // rows are parsed and written on a blocking thread while the body is still arriving, so
+    // the whole upload never has to be held in memory
+    let (sender, receiver) = mpsc::channel(16);
+    let import = tokio::task::spawn_blocking(move || {
+        let mut conn = conn;
+        let mut importer = Importer {
+            conn: &mut conn,
+            user: &user,
+            units,
+            dry_run: query.dry_run.unwrap_or(false),
+            on_conflict: query.on_conflict,
+            pending: Vec::new(),
+            names: HashSet::new(),
+            report: ImportReport::default(),
+            failure: None,
+        };
+        let reader = BufReader::new(ChannelReader {
+            receiver,
+            chunk: Bytes::new(),
+        });
+        match format {
+            Format::Csv => importer.read_csv(reader),
+            Format::Ndjson => importer.read_ndjson(reader),
+        }
+        importer.finish()
+    });
+
+    let mut body = body;
+    let mut size = 0;
+    loop {
+        let message = match body.try_next().await {
+            Ok(Some(mut buf)) => {
+                size += buf.remaining();
+                if size > MAX_IMPORT_SIZE {
+                    Err(format!("imports are limited to {} bytes", MAX_IMPORT_SIZE))
+                } else {
+                    Ok(buf.copy_to_bytes(buf.remaining()))
+                }
+            }
+            Ok(None) => break,
+            Err(_) => Err(String::from("failed to read the request body")),
+        };
+        let failed = message.is_err();
+        // a closed channel means the importer stopped early, its result says why
+        if sender.send(message).await.is_err() || failed {
+            break;
+        }
+    }
+    drop(sender);
+
+    let (report, status) = import
+        .await
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    Ok(reply::with_status(reply::json(&report), status))
+}
+
+// blocking reader over the chunks of a request body, a chunk that failed to arrive is passed
+// on as an I/O error
+struct ChannelReader {
+    receiver: mpsc::Receiver<Result<Bytes, String>>,
+    chunk: Bytes,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(e)) => return Err(io::Error::other(e)),
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk[..len]);
+        self.chunk.advance(len);
+        Ok(len)
+    }
+}
+
+struct Importer<'a> {
+    conn: &'a mut SqliteConnection,
+    user: &'a User,
+    // units of the dimensions in the rows
+    units: Units,
+    dry_run: bool,
+    on_conflict: Option<OnConflict>,
+    pending: Vec<(u64, NewBoat)>,
+    // names of the rows imported so far, dry runs use them in place of the rows they would
+    // have inserted
+    names: HashSet<String>,
+    report: ImportReport,
+    // database failure that stopped the import
+    failure: Option<Error>,
+}
+
+impl Importer<'_> {
+    fn read_csv(&mut self, reader: impl Read) {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(e) => {
+                self.push_error(1, e.to_string());
+                return;
+            }
+        };
+        let mut record = csv::StringRecord::new();
+        loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => {
+                    let line = record.position().map_or(0, |position| position.line());
+                    let row = record
+                        .deserialize::<NewBoat>(Some(&headers))
+                        .map_err(|e| e.to_string());
+                    if !self.push(line, row) {
+                        break;
+                    }
+                }
+                Ok(false) => break,
+                Err(e) => {
+                    let line = e.position().map_or(0, |position| position.line());
+                    let io_error = matches!(e.kind(), csv::ErrorKind::Io(_));
+                    self.push_error(line, e.to_string());
+                    // malformed records can be skipped, a broken body can't
+                    if io_error {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_ndjson(&mut self, reader: impl BufRead) {
+        for (i, line) in reader.lines().enumerate() {
+            let number = i as u64 + 1;
+            match line {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => {
+                    let row = serde_json::from_str::<NewBoat>(&line).map_err(|e| e.to_string());
+                    if !self.push(number, row) {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    self.push_error(number, e.to_string());
+                    break;
+                }
+            }
+        }
+    }
+
+    // queues a parsed row, returns false once the row limit is reached or the import failed
+    fn push(&mut self, line: u64, row: Result<NewBoat, String>) -> bool {
+        if self.report.rows.len() + self.pending.len() >= MAX_IMPORT_ROWS {
+            self.push_error(
+                line,
+                format!("imports are limited to {} rows", MAX_IMPORT_ROWS),
+            );
+            return false;
+        }
+        let units = self.units;
+        let row = row.and_then(|boat| {
+            let mut boat = boat.into_metric(units);
+            match boat.validate() {
+                Ok(()) => Ok(boat),
+                Err(Error::Validation(errors)) => Err(errors
+                    .iter()
+                    .map(|error| error.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")),
+                Err(e) => Err(e.to_string()),
+            }
+        });
+        match row {
+            Ok(boat) => {
+                self.pending.push((line, boat));
+                if self.pending.len() >= BATCH_SIZE {
+                    return self.flush();
+                }
+            }
+            Err(e) => self.push_error(line, e),
+        }
+        true
+    }
+
+    fn push_error(&mut self, line: u64, error: String) {
+        self.report.rows.push(ImportRow {
+            line,
+            status: RowStatus::Error,
+            id: None,
+            error: Some(error),
+        });
+    }
+
+    // writes the pending rows, returns false if that failed
+    fn flush(&mut self) -> bool {
+        let rows = std::mem::take(&mut self.pending);
+        let lines: Vec<u64> = rows.iter().map(|(line, _)| *line).collect();
+        let (user, dry_run, on_conflict) = (self.user, self.dry_run, self.on_conflict);
+        let names = &mut self.names;
+        let results = self
+            .conn
+            .immediate_transaction::<Vec<ImportRow>, Error, _>(|conn| {
+                rows.into_iter()
+                    .map(|(line, boat)| {
+                        let name = boat.name.clone();
+                        let result = import_row(conn, boat, user, dry_run, on_conflict, names);
+                        if matches!(result, Ok((RowStatus::Inserted, _))) {
+                            names.insert(name);
+                        }
+                        Ok(match result {
+                            Ok((status, id)) => ImportRow {
+                                line,
+                                status,
+                                id,
+                                error: None,
+                            },
+                            Err(RowError::Invalid(e)) => ImportRow {
+                                line,
+                                status: RowStatus::Error,
+                                id: None,
+                                error: Some(e),
+                            },
+                            Err(RowError::Database(e)) => return Err(e),
+                        })
+                    })
+                    .collect()
+            });
+        match results {
+            Ok(results) => {
+                self.report.rows.extend(results);
+                true
+            }
+            Err(e) => {
+                let message = e.message();
+                for line in lines {
+                    self.push_error(line, format!("not imported, its batch failed: {}", message));
+                }
+                self.report.aborted = Some(message);
+                self.failure = Some(e);
+                false
+            }
+        }
+    }
+
+    // the report and the status to send it with, which is the one of the failure that
+    // stopped the import, if any
+    fn finish(mut self) -> (ImportReport, StatusCode) {
+        if self.failure.is_none() {
+            self.flush();
+        }
+        let mut report = self.report;
+        report.dry_run = self.dry_run;
+        report.rows.sort_by_key(|row| row.line);
+        for row in &report.rows {
+            match row.status {
+                RowStatus::Inserted => report.inserted += 1,
+                RowStatus::Updated => report.updated += 1,
+                RowStatus::Skipped => report.skipped += 1,
+                RowStatus::Error => report.errors += 1,
+            }
+        }
+        let status = self
+            .failure
+            .map_or(StatusCode::OK, |failure| failure.status());
+        (report, status)
+    }
+}
+
+enum RowError {
+    // the row is reported as failed and the import goes on
+    Invalid(String),
+    // the batch is rolled back and the import stops
+    Database(Error),
+}
+
+impl From<diesel::result::Error> for RowError {
+    fn from(e: diesel::result::Error) -> Self {
+        RowError::Database(Error::from(e))
+    }
+}
+
+fn import_row(
+    conn: &mut SqliteConnection,
+    boat: NewBoat,
+    user: &User,
+    dry_run: bool,
+    on_conflict: Option<OnConflict>,
+    names: &HashSet<String>,
+) -> Result<(RowStatus, Option<i32>), RowError> {
+    if let Some(marina_id) = boat.marina_id {
+        let marina: Option<i32> = marinas::table
+            .find(marina_id)
+            .select(marinas::id)
+            .first(conn)
+            .optional()?;
+        if marina.is_none() {
+            return Err(RowError::Invalid(format!("unknown marina {}", marina_id)));
+        }
+    }
+    let existing: Option<Boat> = boats::table
+        .filter(boats::name.eq(&boat.name))
+        .select(Boat::as_select())
+        .first(conn)
+        .optional()?;
+    let taken = existing.is_some() || (dry_run && names.contains(&boat.name));
+
+    match (existing, on_conflict) {
+        _ if !taken => {
+            if dry_run {
+                return Ok((RowStatus::Inserted, None));
+            }
+            let now = Utc::now();
+            let id = diesel::insert_into(boats::table)
+                .values((
+                    NewBoat {
+                        owner_email: Some(user.email.clone()),
+                        ..boat
+                    },
+                    boats::created_at.eq(now),
+                    boats::updated_at.eq(now),
+                ))
+                .returning(boats::id)
+                .get_result(conn)?;
+            Ok((RowStatus::Inserted, Some(id)))
+        }
+        (_, None) => Err(RowError::Invalid(format!(
+            "a boat named {:?} already exists",
+            boat.name
+        ))),
+        (existing, Some(OnConflict::Skip)) => {
+            Ok((RowStatus::Skipped, existing.map(|existing| existing.id)))
+        }
+        // the name was taken by an earlier row of this dry run
+        (None, Some(OnConflict::Update)) => Ok((RowStatus::Updated, None)),
+        (Some(existing), Some(OnConflict::Update)) => {
+            if existing.deleted_at.is_some() {
+                return Err(RowError::Invalid(format!(
+                    "a deleted boat named {:?} exists",
+                    boat.name
+                )));
+            }
+            if check_owner(existing.owner_email.as_ref(), user).is_err() {
+                return Err(RowError::Invalid(format!(
+                    "no permission to update boat {}",
+                    existing.id
+                )));
+            }
+            if dry_run {
+                return Ok((RowStatus::Updated, Some(existing.id)));
+            }
+            record_revision(conn, &existing, RevisionAction::Update, user)
+                .map_err(RowError::Database)?;
+            // imported rows describe the whole boat, so missing optional values are cleared,
+            // except for the availability flag
+            let is_available = boat.is_available.unwrap_or(existing.is_available);
+            diesel::update(boats::table.find(existing.id))
+                .set((
+                    BoatState {
+                        is_available,
+                        ..BoatState::from(boat)
+                    },
+                    boats::version.eq(boats::version + 1),
+                    boats::updated_at.eq(Utc::now()),
+                ))
+                .execute(conn)?;
+            Ok((RowStatus::Updated, Some(existing.id)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_connection, test_user};
+    use diesel::connection::SimpleConnection;
+
+    fn import(conn: &mut SqliteConnection, user: &User, rows: &str) -> (ImportReport, StatusCode) {
+        let mut importer = Importer {
+            conn,
+            user,
+            units: Units::Metric,
+            dry_run: false,
+            on_conflict: None,
+            pending: Vec::new(),
+            names: HashSet::new(),
+            report: ImportReport::default(),
+            failure: None,
+        };
+        importer.read_ndjson(rows.as_bytes());
+        importer.finish()
+    }
+
+    fn rows(count: usize, name: impl Fn(usize) -> String) -> String {
+        (1..=count)
+            .map(|i| {
+                format!(
+                    "{{\"name\":\"{}\",\"make\":\"Dufour\",\"model\":\"382\",\"year\":2018}}\n",
+                    name(i)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_rows_by_line() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let rows = "{\"name\":\"Aurora\",\"make\":\"Dufour\",\"model\":\"382\",\"year\":2018}\n\
+            \n\
+            {\"name\":\"Bora\",\"make\":\"Dufour\",\"model\":\"382\",\"year\":1700}\n\
+            {\"name\":\"Aurora\",\"make\":\"Dufour\",\"model\":\"382\",\"year\":2018}\n";
+        let (report, status) = import(&mut conn, &user, rows);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((report.inserted, report.errors), (1, 2));
+        let lines: Vec<(u64, RowStatus)> = report
+            .rows
+            .iter()
+            .map(|row| (row.line, row.status))
+            .collect();
+        assert!(
+            lines
+                == [
+                    (1, RowStatus::Inserted),
+                    (3, RowStatus::Error),
+                    (4, RowStatus::Error)
+                ]
+        );
+        assert!(report.aborted.is_none());
+    }
+
+    #[test]
+    fn keeps_the_batches_written_before_a_failure() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        conn.batch_execute(
+            "CREATE TRIGGER fail_import BEFORE INSERT ON boats WHEN NEW.name = 'Boat 120' \
+             BEGIN SELECT RAISE(ABORT, 'disk full'); END;",
+        )
+        .unwrap();
+
+        let (report, status) = import(&mut conn, &user, &rows(250, |i| format!("Boat {}", i)));
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(report.aborted.is_some());
+        // the first batch is written, the second one rolled back and the rest never read
+        assert_eq!((report.inserted, report.errors), (BATCH_SIZE, BATCH_SIZE));
+        assert_eq!(report.rows.len(), 2 * BATCH_SIZE);
+        assert!(report.rows[..BATCH_SIZE]
+            .iter()
+            .all(|row| row.status == RowStatus::Inserted && row.id.is_some()));
+        assert!(report.rows[BATCH_SIZE..]
+            .iter()
+            .all(|row| row.status == RowStatus::Error));
+        let stored: i64 = boats::table.count().get_result(&mut conn).unwrap();
+        assert_eq!(stored, BATCH_SIZE as i64);
+    }
+}
@@ -46,7 +46,7 @@ pub async fn decode_token(
     let jwt_secret = get_config()
         .get_string("jwt.secret")
         .map_err(|_| reject::custom(Error::JWTCreationFailed))?;
-    let jwt = jwt_from_header(&headers).map_err(|e| reject::custom(e))?;
+    let jwt = jwt_from_header(&headers).map_err(reject::custom)?;
     let decoded = decode::<Claims>(
         &jwt,
         &DecodingKey::from_secret(jwt_secret.as_bytes()),
@@ -0,0 +1,269 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, authorize_boat_owner},
+    models::{
+        maintenance::{
+            MaintenanceInterval, MaintenanceRecord, MaintenanceWindow, NewMaintenanceRecord,
+            NewMaintenanceWindow, OverdueService, ServiceStatus,
+        },
+        user::User,
+    },
+    schema::{boats, bookings, maintenance_intervals, maintenance_records, maintenance_windows},
+};
+use chrono::{Duration, Utc};
+use diesel::{
+    sql_types::{Bool, Text},
+    ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper,
+};
+use warp::{http::StatusCode, reject, reply};
+
+const MAX_INTERVALS: usize = 50;
+
+// service records include costs, so only the owner can see them
+pub async fn get_records(
+    boat_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let records: Vec<MaintenanceRecord> = maintenance_records::table
+        .filter(maintenance_records::boat_id.eq(boat_id))
+        .order((
+            maintenance_records::performed_at.desc(),
+            maintenance_records::id.desc(),
+        ))
+        .select(MaintenanceRecord::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&records))
+}
+
+pub async fn create_record(
+    boat_id: i32,
+    mut record: NewMaintenanceRecord,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    record.validate().map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let record: MaintenanceRecord = diesel::insert_into(maintenance_records::table)
+        .values(NewMaintenanceRecord { boat_id, ..record })
+        .returning(MaintenanceRecord::as_returning())
+        .get_result(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::with_status(
+        reply::json(&record),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn delete_record(
+    boat_id: i32,
+    record_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let deleted = diesel::delete(
+        maintenance_records::table
+            .filter(maintenance_records::id.eq(record_id))
+            .filter(maintenance_records::boat_id.eq(boat_id)),
+    )
+    .execute(&mut conn)
+    .map_err(|e| reject::custom(Error::from(e)))?;
+    if deleted == 0 {
+        return Err(reject::custom(Error::NotFound));
+    }
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+pub async fn get_windows(
+    boat_id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::id)
+        .first::<i32>(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    let windows: Vec<MaintenanceWindow> = maintenance_windows::table
+        .filter(maintenance_windows::boat_id.eq(boat_id))
+        .order(maintenance_windows::starts_at.asc())
+        .select(MaintenanceWindow::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&windows))
+}
+
+pub async fn create_window(
+    boat_id: i32,
+    mut window: NewMaintenanceWindow,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    window.validate().map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    // same write lock as bookings, so a booking can't slip into the window while it is checked
+    let window = conn
+        .immediate_transaction::<MaintenanceWindow, Error, _>(|conn| {
+            authorize_boat_owner(conn, boat_id, &user)?;
+            let overlapping_bookings: i64 = bookings::table
+                .filter(bookings::boat_id.eq(boat_id))
+                .filter(bookings::starts_at.lt(window.ends_at))
+                .filter(bookings::ends_at.gt(window.starts_at))
+                .count()
+                .get_result(conn)?;
+            if overlapping_bookings > 0 {
+                return Err(Error::Conflict);
+            }
+            Ok(diesel::insert_into(maintenance_windows::table)
+                .values(NewMaintenanceWindow { boat_id, ..window })
+                .returning(MaintenanceWindow::as_returning())
+                .get_result(conn)?)
+        })
+        .map_err(reject::custom)?;
+    Ok(reply::with_status(
+        reply::json(&window),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn delete_window(
+    boat_id: i32,
+    window_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let deleted = diesel::delete(
+        maintenance_windows::table
+            .filter(maintenance_windows::id.eq(window_id))
+            .filter(maintenance_windows::boat_id.eq(boat_id)),
+    )
+    .execute(&mut conn)
+    .map_err(|e| reject::custom(Error::from(e)))?;
+    if deleted == 0 {
+        return Err(reject::custom(Error::NotFound));
+    }
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+pub async fn get_intervals(
+    boat_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let intervals: Vec<MaintenanceInterval> = maintenance_intervals::table
+        .filter(maintenance_intervals::boat_id.eq(boat_id))
+        .order(maintenance_intervals::service_type.asc())
+        .select(MaintenanceInterval::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&intervals))
+}
+
+// replaces every service interval of a boat
+pub async fn set_intervals(
+    boat_id: i32,
+    intervals: Vec<MaintenanceInterval>,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if intervals.len() > MAX_INTERVALS {
+        return Err(reject::custom(Error::PayloadTooLarge));
+    }
+    let mut intervals = intervals
+        .into_iter()
+        .map(|interval| MaintenanceInterval {
+            boat_id,
+            ..interval
+        })
+        .collect::<Vec<_>>();
+    for interval in &mut intervals {
+        interval.validate().map_err(reject::custom)?;
+    }
+
+    let mut conn = acquire_connection(&pool).await?;
+    conn.immediate_transaction::<(), Error, _>(|conn| {
+        authorize_boat_owner(conn, boat_id, &user)?;
+        diesel::delete(
+            maintenance_intervals::table.filter(maintenance_intervals::boat_id.eq(boat_id)),
+        )
+        .execute(conn)?;
+        // a service type listed twice violates the primary key and is reported as a conflict
+        diesel::insert_into(maintenance_intervals::table)
+            .values(&intervals)
+            .execute(conn)?;
+        Ok(())
+    })
+    .map_err(reject::custom)?;
+    Ok(reply::json(&intervals))
+}
+
+// services that are due by elapsed time or engine hours, admins see every boat and everyone
+// else only their own
+pub async fn get_overdue(
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let statuses: Vec<ServiceStatus> = diesel::sql_query(
+        "SELECT i.boat_id, b.name AS boat_name, b.created_at AS boat_created_at, \
+            i.service_type, i.every_days, i.every_engine_hours, \
+            (SELECT max(r.performed_at) FROM maintenance_records r \
+             WHERE r.boat_id = i.boat_id AND r.service_type = i.service_type) \
+                AS last_performed_at, \
+            (SELECT r.engine_hours FROM maintenance_records r \
+             WHERE r.boat_id = i.boat_id AND r.service_type = i.service_type \
+                AND r.engine_hours IS NOT NULL \
+             ORDER BY r.performed_at DESC LIMIT 1) AS last_engine_hours, \
+            (SELECT max(r.engine_hours) FROM maintenance_records r \
+             WHERE r.boat_id = i.boat_id) AS engine_hours \
+         FROM maintenance_intervals i JOIN boats b ON b.id = i.boat_id \
+         WHERE b.deleted_at IS NULL AND (? OR b.owner_email = ?) \
+         ORDER BY i.boat_id, i.service_type",
+    )
+    .bind::<Bool, _>(user.is_admin)
+    .bind::<Text, _>(&user.email)
+    .load(&mut conn)
+    .map_err(|e| reject::custom(Error::from(e)))?;
+
+    let now = Utc::now();
+    let overdue: Vec<OverdueService> = statuses
+        .into_iter()
+        .filter_map(|status| {
+            // services that were never done are counted from when the boat was added
+            let due_at = status.every_days.map(|days| {
+                status.last_performed_at.unwrap_or(status.boat_created_at)
+                    + Duration::days(days.into())
+            });
+            let engine_hours_since = status
+                .every_engine_hours
+                .and(status.engine_hours)
+                .map(|hours| hours - status.last_engine_hours.unwrap_or(0.0));
+            let due_by_time = due_at.is_some_and(|due_at| due_at <= now);
+            let due_by_hours = status
+                .every_engine_hours
+                .zip(engine_hours_since)
+                .is_some_and(|(every, since)| since >= every);
+            (due_by_time || due_by_hours).then_some(OverdueService {
+                boat_id: status.boat_id,
+                boat_name: status.boat_name,
+                service_type: status.service_type,
+                last_performed_at: status.last_performed_at,
+                due_at,
+                engine_hours_since,
+            })
+        })
+        .collect();
+    Ok(reply::json(&overdue))
+}
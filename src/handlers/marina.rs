@@ -0,0 +1,103 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::{helpers::acquire_connection, revision::record_revision},
+    models::{
+        boat::Boat,
+        marina::{valid_latitude, valid_longitude, Marina, NewMarina},
+        revision::RevisionAction,
+        user::User,
+    },
+    schema::{boats, marinas},
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use warp::{http::StatusCode, reject, reply};
+
+pub async fn get_marina(
+    id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let marina: Marina = marinas::table
+        .find(id)
+        .first(&mut conn)
+        .map_err(|_| reject::custom(Error::NotFound))?;
+    Ok(reply::json(&marina))
+}
+
+pub async fn get_all_marinas(
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let marinas: Vec<Marina> = marinas::table
+        .order(marinas::name)
+        .load(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    Ok(reply::json(&marinas))
+}
+
+// marinas are shared by every owner, so only admins can change them
+pub async fn create_marina(
+    marina: NewMarina,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !user.is_admin {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    if marina.name.trim().is_empty()
+        || !valid_latitude(marina.latitude)
+        || !valid_longitude(marina.longitude)
+        || marina.timezone.parse::<Tz>().is_err()
+    {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let mut conn = acquire_connection(&pool).await?;
+    let marina: Marina = diesel::insert_into(marinas::table)
+        .values(&marina)
+        .returning(Marina::as_returning())
+        .get_result(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    Ok(reply::with_status(
+        reply::json(&marina),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn delete_marina(
+    id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !user.is_admin {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    let mut conn = acquire_connection(&pool).await?;
+    conn.immediate_transaction::<(), Error, _>(|conn| {
+        // boats based at the marina are left without a home marina. They are cleared here
+        // rather than by the foreign key so that they get a new version and revision
+        let based: Vec<Boat> = boats::table
+            .filter(boats::marina_id.eq(id))
+            .select(Boat::as_select())
+            .load(conn)?;
+        for boat in &based {
+            record_revision(conn, boat, RevisionAction::Update, &user)?;
+        }
+        diesel::update(boats::table.filter(boats::marina_id.eq(id)))
+            .set((
+                boats::marina_id.eq(None::<i32>),
+                boats::version.eq(boats::version + 1),
+                boats::updated_at.eq(Utc::now()),
+            ))
+            .execute(conn)?;
+        let deleted = diesel::delete(marinas::table.find(id)).execute(conn)?;
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    })
+    .map_err(reject::custom)?;
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
@@ -0,0 +1,288 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, authorize_boat_owner},
+    media::{process, ProcessedMedia, MAX_FILE_SIZE},
+    models::{
+        media::{Media, NewMedia},
+        user::User,
+    },
+    schema::{boat_media, boats},
+    storage::SharedStorage,
+};
+use chrono::Utc;
+use diesel::{dsl::max, Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use futures_util::TryStreamExt;
+use log::error;
+use warp::{
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderValue, StatusCode,
+    },
+    hyper::body::Buf,
+    multipart::{FormData, Part},
+    reject, reply, Reply,
+};
+
+// upper bound for a whole multipart form, the file limit itself is checked after reading
+pub const MAX_FORM_SIZE: u64 = MAX_FILE_SIZE + 64 * 1024;
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+pub async fn get_media(
+    boat_id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::id)
+        .first::<i32>(&mut conn)
+        .map_err(|_| reject::custom(Error::NotFound))?;
+    let media: Vec<Media> = boat_media::table
+        .filter(boat_media::boat_id.eq(boat_id))
+        .order((boat_media::sort_order.asc(), boat_media::id.asc()))
+        .select(Media::as_select())
+        .load(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    Ok(reply::json(&media))
+}
+
+pub async fn get_media_file(
+    boat_id: i32,
+    media_id: i32,
+    storage: SharedStorage,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let media = find_media(boat_id, media_id, &pool).await?;
+    let key = media.storage_key.clone();
+    let data = read(storage, key).await?;
+    Ok(file_response(
+        data,
+        &media.content_type,
+        Some(&media.file_name),
+    ))
+}
+
+pub async fn get_media_thumbnail(
+    boat_id: i32,
+    media_id: i32,
+    storage: SharedStorage,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let media = find_media(boat_id, media_id, &pool).await?;
+    let key = media.thumbnail_key.ok_or(reject::custom(Error::NotFound))?;
+    let data = read(storage, key).await?;
+    Ok(file_response(data, "image/jpeg", None))
+}
+
+pub async fn upload_media(
+    boat_id: i32,
+    form: FormData,
+    user: User,
+    storage: SharedStorage,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut file: Option<(Option<String>, Vec<u8>)> = None;
+    let mut sort_order: Option<i32> = None;
+    let mut parts = form;
+    while let Some(part) = parts
+        .try_next()
+        .await
+        .map_err(|_| reject::custom(Error::InvalidParameter))?
+    {
+        match part.name() {
+            "file" => {
+                let file_name = part.filename().map(String::from);
+                file = Some((file_name, read_part(part).await?));
+            }
+            "sort_order" => {
+                let value = String::from_utf8(read_part(part).await?)
+                    .map_err(|_| reject::custom(Error::InvalidParameter))?;
+                sort_order = Some(
+                    value
+                        .trim()
+                        .parse()
+                        .map_err(|_| reject::custom(Error::InvalidParameter))?,
+                );
+            }
+            _ => {}
+        }
+    }
+    let (file_name, data) = file.ok_or(reject::custom(Error::InvalidParameter))?;
+
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+
+    // decoding and re-encoding images is CPU bound, keep it off the async workers
+    let processed = tokio::task::spawn_blocking(move || process(data))
+        .await
+        .map_err(|_| reject::custom(Error::StorageFailed))?
+        .map_err(reject::custom)?;
+    let file_name = sanitize_file_name(file_name, processed.extension);
+    let (storage_key, thumbnail_key) = store(boat_id, &processed, storage.clone()).await?;
+
+    let result = conn.transaction::<Media, Error, _>(|conn| {
+        let sort_order = match sort_order {
+            Some(sort_order) => sort_order,
+            None => boat_media::table
+                .filter(boat_media::boat_id.eq(boat_id))
+                .select(max(boat_media::sort_order))
+                .first::<Option<i32>>(conn)?
+                .map_or(0, |sort_order| sort_order + 1),
+        };
+        Ok(diesel::insert_into(boat_media::table)
+            .values(NewMedia {
+                boat_id,
+                storage_key: storage_key.clone(),
+                thumbnail_key: thumbnail_key.clone(),
+                file_name,
+                content_type: processed.content_type.to_owned(),
+                size: processed.data.len() as i64,
+                checksum: processed.checksum.clone(),
+                sort_order,
+                created_at: Utc::now(),
+            })
+            .returning(Media::as_returning())
+            .get_result(conn)?)
+    });
+    match result {
+        Ok(media) => Ok(reply::with_status(reply::json(&media), StatusCode::CREATED)),
+        Err(e) => {
+            // don't leave files behind that no row refers to
+            remove(storage, vec![Some(storage_key), thumbnail_key]).await;
+            Err(reject::custom(e))
+        }
+    }
+}
+
+pub async fn delete_media(
+    boat_id: i32,
+    media_id: i32,
+    user: User,
+    storage: SharedStorage,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let media = find_media(boat_id, media_id, &pool).await?;
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    diesel::delete(boat_media::table.find(media.id))
+        .execute(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    remove(storage, vec![Some(media.storage_key), media.thumbnail_key]).await;
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+// removes stored files after their rows are gone, failures are only logged since the files
+// are unreachable either way
+pub async fn remove(storage: SharedStorage, keys: Vec<Option<String>>) {
+    let result = tokio::task::spawn_blocking(move || {
+        keys.into_iter()
+            .flatten()
+            .try_for_each(|key| storage.delete(&key))
+    })
+    .await;
+    if !matches!(result, Ok(Ok(()))) {
+        error!("Failed to remove media files");
+    }
+}
+
+async fn find_media(
+    boat_id: i32,
+    media_id: i32,
+    pool: &SharedConnectionPool,
+) -> Result<Media, warp::Rejection> {
+    let mut conn = acquire_connection(pool).await?;
+    // media of boats in the trash go with them
+    boat_media::table
+        .inner_join(boats::table)
+        .filter(boat_media::boat_id.eq(boat_id))
+        .filter(boat_media::id.eq(media_id))
+        .filter(boats::deleted_at.is_null())
+        .select(Media::as_select())
+        .first(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))
+}
+
+async fn read_part(part: Part) -> Result<Vec<u8>, warp::Rejection> {
+    part.stream()
+        .try_fold(Vec::new(), |mut data, buf| async move {
+            data.extend_from_slice(buf.chunk());
+            Ok(data)
+        })
+        .await
+        .map_err(|_| reject::custom(Error::InvalidParameter))
+}
+
+async fn read(storage: SharedStorage, key: String) -> Result<Vec<u8>, warp::Rejection> {
+    tokio::task::spawn_blocking(move || storage.get(&key))
+        .await
+        .map_err(|_| reject::custom(Error::StorageFailed))?
+        .map_err(reject::custom)
+}
+
+async fn store(
+    boat_id: i32,
+    processed: &ProcessedMedia,
+    storage: SharedStorage,
+) -> Result<(String, Option<String>), warp::Rejection> {
+    let name = uuid::Uuid::new_v4();
+    let storage_key = format!("boats/{}/{}.{}", boat_id, name, processed.extension);
+    let thumbnail = processed
+        .thumbnail
+        .clone()
+        .map(|data| (format!("boats/{}/{}-thumbnail.jpg", boat_id, name), data));
+    let thumbnail_key = thumbnail.as_ref().map(|(key, _)| key.clone());
+    let key = storage_key.clone();
+    let data = processed.data.clone();
+    tokio::task::spawn_blocking(move || {
+        storage.put(&key, &data)?;
+        if let Some((thumbnail_key, thumbnail)) = thumbnail {
+            storage.put(&thumbnail_key, &thumbnail)?;
+        }
+        Ok::<_, Error>(())
+    })
+    .await
+    .map_err(|_| reject::custom(Error::StorageFailed))?
+    .map_err(reject::custom)?;
+    Ok((storage_key, thumbnail_key))
+}
+
+fn sanitize_file_name(file_name: Option<String>, extension: &str) -> String {
+    // keep the base name only, and only characters that are safe in a Content-Disposition header
+    let file_name: String = file_name
+        .as_deref()
+        .and_then(|name| name.rsplit(['/', '\\']).next())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ' '))
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+    match file_name.trim() {
+        "" | "." | ".." => format!("upload.{}", extension),
+        name => name.to_owned(),
+    }
+}
+
+fn file_response(
+    data: Vec<u8>,
+    content_type: &str,
+    file_name: Option<&str>,
+) -> warp::reply::Response {
+    let mut response = warp::reply::Response::new(data.into());
+    let headers = response.headers_mut();
+    if let Ok(content_type) = HeaderValue::from_str(content_type) {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    // browsers must not second-guess the sniffed type of user uploads
+    headers.insert(
+        "X-Content-Type-Options",
+        HeaderValue::from_static("nosniff"),
+    );
+    if let Some(disposition) = file_name
+        .and_then(|name| HeaderValue::from_str(&format!("inline; filename=\"{}\"", name)).ok())
+    {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    response.into_response()
+}
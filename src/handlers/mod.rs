@@ -1,4 +1,16 @@
+pub mod availability;
+pub mod batch;
 pub mod boat;
+pub mod booking;
+pub mod calendar;
 pub mod helpers;
+pub mod import;
 pub mod jwt;
+pub mod maintenance;
+pub mod marina;
+pub mod media;
+pub mod pricing;
+pub mod review;
+pub mod revision;
+pub mod tag;
 pub mod user;
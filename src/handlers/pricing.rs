@@ -0,0 +1,230 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, authorize_boat_owner},
+    models::{
+        pricing::{NewSeasonalRate, Quote, QuoteRequest, RateCard, Rates, SeasonalRate},
+        user::User,
+    },
+    pricing::{billed_until, quote, validate_rate_card},
+    schema::{boats, rate_cards, seasonal_rates},
+};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection};
+use warp::{http::StatusCode, reject, reply};
+
+pub async fn get_rates(
+    boat_id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let rate_card: RateCard = rate_cards::table
+        .find(boat_id)
+        .first(&mut conn)
+        .map_err(|_| reject::custom(Error::NotFound))?;
+    let seasons: Vec<SeasonalRate> = seasonal_rates::table
+        .filter(seasonal_rates::boat_id.eq(boat_id))
+        .order(seasonal_rates::starts_on.asc())
+        .load(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    Ok(reply::json(&Rates { rate_card, seasons }))
+}
+
+pub async fn set_rate_card(
+    boat_id: i32,
+    rate_card: RateCard,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let rate_card = RateCard {
+        boat_id,
+        ..rate_card
+    };
+    validate_rate_card(&rate_card).map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    diesel::replace_into(rate_cards::table)
+        .values(&rate_card)
+        .execute(&mut conn)
+        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    Ok(reply::json(&rate_card))
+}
+
+pub async fn create_season(
+    boat_id: i32,
+    season: NewSeasonalRate,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if season.name.trim().is_empty()
+        || season.ends_on < season.starts_on
+        || season.multiplier <= 0.0
+    {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let season = conn
+        .immediate_transaction::<SeasonalRate, Error, _>(|conn| {
+            rate_cards::table
+                .find(boat_id)
+                .select(rate_cards::boat_id)
+                .first::<i32>(conn)?;
+            // a day can only belong to one season, so multipliers never stack
+            let overlapping: i64 = seasonal_rates::table
+                .filter(seasonal_rates::boat_id.eq(boat_id))
+                .filter(seasonal_rates::starts_on.le(season.ends_on))
+                .filter(seasonal_rates::ends_on.ge(season.starts_on))
+                .count()
+                .get_result(conn)?;
+            if overlapping > 0 {
+                return Err(Error::Conflict);
+            }
+            Ok(diesel::insert_into(seasonal_rates::table)
+                .values((
+                    seasonal_rates::boat_id.eq(boat_id),
+                    seasonal_rates::name.eq(season.name.trim()),
+                    seasonal_rates::starts_on.eq(season.starts_on),
+                    seasonal_rates::ends_on.eq(season.ends_on),
+                    seasonal_rates::multiplier.eq(season.multiplier),
+                ))
+                .returning(SeasonalRate::as_returning())
+                .get_result(conn)?)
+        })
+        .map_err(reject::custom)?;
+    Ok(reply::with_status(
+        reply::json(&season),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn delete_season(
+    boat_id: i32,
+    season_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let deleted = diesel::delete(
+        seasonal_rates::table
+            .filter(seasonal_rates::id.eq(season_id))
+            .filter(seasonal_rates::boat_id.eq(boat_id)),
+    )
+    .execute(&mut conn)
+    .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+    if deleted == 0 {
+        return Err(reject::custom(Error::NotFound));
+    }
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+pub async fn create_quote(
+    boat_id: i32,
+    request: QuoteRequest,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let quote = quote_boat(&mut conn, boat_id, &request).map_err(reject::custom)?;
+    Ok(reply::json(&quote))
+}
+
+// prices a rental of a boat that isn't in the trash
+fn quote_boat(
+    conn: &mut SqliteConnection,
+    boat_id: i32,
+    request: &QuoteRequest,
+) -> Result<Quote, Error> {
+    let rate_card: RateCard = rate_cards::table
+        .inner_join(boats::table)
+        .filter(rate_cards::boat_id.eq(boat_id))
+        .filter(boats::deleted_at.is_null())
+        .select(RateCard::as_select())
+        .first(conn)?;
+    // rentals shorter than the minimum are charged, and priced by season, past their end
+    let billed_until = billed_until(&rate_card, request.starts_at, request.ends_at)?;
+    let seasons: Vec<SeasonalRate> = seasonal_rates::table
+        .filter(seasonal_rates::boat_id.eq(boat_id))
+        .filter(seasonal_rates::starts_on.le(billed_until.date_naive()))
+        .filter(seasonal_rates::ends_on.ge(request.starts_at.date_naive()))
+        .load(conn)?;
+    quote(&rate_card, &seasons, request.starts_at, request.ends_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{test_connection, test_user},
+        handlers::boat::{insert_boat, trash_boat},
+    };
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    // a boat charged for at least two days, with a season starting on its second day
+    fn priced_boat(conn: &mut SqliteConnection, user: &User) -> i32 {
+        let boat = serde_json::from_value(json!({
+            "name": "Aurora",
+            "make": "Jeanneau",
+            "model": "Sun Odyssey 410",
+            "year": 2021,
+        }))
+        .unwrap();
+        let boat_id = insert_boat(conn, boat, user).unwrap().id;
+        diesel::insert_into(rate_cards::table)
+            .values(RateCard {
+                boat_id,
+                currency: String::from("EUR"),
+                hourly_rate: Some(1_000),
+                daily_rate: None,
+                weekly_rate: None,
+                min_hours: 48,
+            })
+            .execute(conn)
+            .unwrap();
+        diesel::insert_into(seasonal_rates::table)
+            .values((
+                seasonal_rates::boat_id.eq(boat_id),
+                seasonal_rates::name.eq("Summer"),
+                seasonal_rates::starts_on.eq("2024-06-02".parse::<NaiveDate>().unwrap()),
+                seasonal_rates::ends_on.eq("2024-08-31".parse::<NaiveDate>().unwrap()),
+                seasonal_rates::multiplier.eq(2.0),
+            ))
+            .execute(conn)
+            .unwrap();
+        boat_id
+    }
+
+    fn request() -> QuoteRequest {
+        QuoteRequest {
+            starts_at: "2024-06-01T10:00:00Z".parse().unwrap(),
+            ends_at: "2024-06-01T12:00:00Z".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn applies_seasons_up_to_the_end_of_the_billed_hours() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let boat_id = priced_boat(&mut conn, &user);
+
+        let quote = quote_boat(&mut conn, boat_id, &request()).unwrap();
+        assert_eq!(quote.billed_hours, 48);
+        // 34 of the 48 billed hours fall into the season
+        let season = quote.items.iter().find(|item| item.quantity == 34).unwrap();
+        assert_eq!(season.amount, 34_000);
+        assert_eq!(quote.total, 82_000);
+    }
+
+    #[test]
+    fn does_not_quote_boats_in_the_trash() {
+        let mut conn = test_connection();
+        let user = test_user(&mut conn, "owner@example.com", false);
+        let boat_id = priced_boat(&mut conn, &user);
+        trash_boat(&mut conn, boat_id, Some("*"), &user).unwrap();
+
+        assert!(matches!(
+            quote_boat(&mut conn, boat_id, &request()),
+            Err(Error::NotFound)
+        ));
+    }
+}
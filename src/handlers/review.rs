@@ -0,0 +1,175 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::acquire_connection,
+    models::{
+        review::{NewReview, Review, ReviewQuery},
+        user::User,
+    },
+    pagination::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, TOTAL_COUNT_HEADER},
+    schema::{boats, reviews},
+};
+use chrono::Utc;
+use diesel::{
+    sql_types::{Integer, TimestamptzSqlite},
+    ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection,
+};
+use warp::{http::StatusCode, reject, reply};
+
+pub async fn get_reviews(
+    boat_id: i32,
+    query: ReviewQuery,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(reject::custom(Error::InvalidParameter));
+    }
+    let mut conn = acquire_connection(&pool).await?;
+    boat_owner(&mut conn, boat_id).map_err(reject::custom)?;
+    let total: i64 = reviews::table
+        .filter(reviews::boat_id.eq(boat_id))
+        .count()
+        .get_result(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    let reviews: Vec<Review> = reviews::table
+        .filter(reviews::boat_id.eq(boat_id))
+        .order((reviews::created_at.desc(), reviews::id.desc()))
+        .limit(limit.into())
+        .offset(query.offset.unwrap_or(0).into())
+        .select(Review::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::with_header(
+        reply::json(&reviews),
+        TOTAL_COUNT_HEADER,
+        total.to_string(),
+    ))
+}
+
+// every user can review a boat once, except for its owner
+pub async fn create_review(
+    boat_id: i32,
+    mut review: NewReview,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    review.validate().map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    let review = conn
+        .immediate_transaction::<Review, Error, _>(|conn| {
+            if boat_owner(conn, boat_id)? == Some(user.email.clone()) {
+                return Err(Error::NoPermission);
+            }
+            let now = Utc::now();
+            // a second review by the same user violates the unique key and is a conflict
+            let review = diesel::insert_into(reviews::table)
+                .values((
+                    reviews::boat_id.eq(boat_id),
+                    reviews::user_email.eq(&user.email),
+                    reviews::rating.eq(review.rating),
+                    reviews::text.eq(&review.text),
+                    reviews::created_at.eq(now),
+                    reviews::updated_at.eq(now),
+                ))
+                .returning(Review::as_returning())
+                .get_result(conn)?;
+            update_rating(conn, boat_id)?;
+            Ok(review)
+        })
+        .map_err(reject::custom)?;
+    Ok(reply::with_status(
+        reply::json(&review),
+        StatusCode::CREATED,
+    ))
+}
+
+// reviews can only be edited by their author
+pub async fn update_review(
+    boat_id: i32,
+    review_id: i32,
+    mut review: NewReview,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    review.validate().map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    let review = conn
+        .immediate_transaction::<Review, Error, _>(|conn| {
+            boat_owner(conn, boat_id)?;
+            if review_author(conn, boat_id, review_id)? != user.email {
+                return Err(Error::NoPermission);
+            }
+            let review = diesel::update(reviews::table.find(review_id))
+                .set((
+                    reviews::rating.eq(review.rating),
+                    reviews::text.eq(&review.text),
+                    reviews::updated_at.eq(Utc::now()),
+                ))
+                .returning(Review::as_returning())
+                .get_result(conn)?;
+            update_rating(conn, boat_id)?;
+            Ok(review)
+        })
+        .map_err(reject::custom)?;
+    Ok(reply::json(&review))
+}
+
+// reviews can be removed by their author or an admin
+pub async fn delete_review(
+    boat_id: i32,
+    review_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    conn.immediate_transaction::<(), Error, _>(|conn| {
+        boat_owner(conn, boat_id)?;
+        if review_author(conn, boat_id, review_id)? != user.email && !user.is_admin {
+            return Err(Error::NoPermission);
+        }
+        diesel::delete(reviews::table.find(review_id)).execute(conn)?;
+        update_rating(conn, boat_id)
+    })
+    .map_err(reject::custom)?;
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+// owner of a boat that isn't in the trash
+fn boat_owner(conn: &mut SqliteConnection, boat_id: i32) -> Result<Option<String>, Error> {
+    Ok(boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::owner_email)
+        .first(conn)?)
+}
+
+fn review_author(
+    conn: &mut SqliteConnection,
+    boat_id: i32,
+    review_id: i32,
+) -> Result<String, Error> {
+    Ok(reviews::table
+        .filter(reviews::id.eq(review_id))
+        .filter(reviews::boat_id.eq(boat_id))
+        .select(reviews::user_email)
+        .first(conn)?)
+}
+
+// recomputes the rating of a boat from its reviews. The rating is part of the boat, so its
+// version and modification time move forward like on any other write
+fn update_rating(conn: &mut SqliteConnection, boat_id: i32) -> Result<(), Error> {
+    diesel::sql_query(
+        "UPDATE boats SET \
+            rating = (SELECT round(avg(reviews.rating), 2) FROM reviews \
+                WHERE reviews.boat_id = boats.id), \
+            review_count = (SELECT count(*) FROM reviews WHERE reviews.boat_id = boats.id), \
+            version = version + 1, \
+            updated_at = ? \
+         WHERE id = ?",
+    )
+    .bind::<TimestamptzSqlite, _>(Utc::now())
+    .bind::<Integer, _>(boat_id)
+    .execute(conn)?;
+    Ok(())
+}
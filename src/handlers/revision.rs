@@ -0,0 +1,332 @@
+use crate::{
+    conditional::{check_if_match, with_etag},
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, authorize_boat_owner, check_owner},
+    models::{
+        boat::{Boat, BoatState, NewBoat},
+        revision::{FieldChange, NewRevision, Revision, RevisionAction, RevisionEntry},
+        user::User,
+    },
+    schema::{boat_revisions, boats},
+    units::{label_units, Units},
+};
+use chrono::Utc;
+use diesel::{
+    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper, SqliteConnection,
+};
+use serde_json::{Map, Value};
+use warp::{reject, reply};
+
+// fields that change with every write or with the boat's reviews, rather than by editing it
+const BOOKKEEPING_FIELDS: [&str; 5] = ["id", "version", "updated_at", "rating", "review_count"];
+
+// records the state of a boat before `action` changes it
+pub fn record_revision(
+    conn: &mut SqliteConnection,
+    boat: &Boat,
+    action: RevisionAction,
+    user: &User,
+) -> Result<(), Error> {
+    let snapshot = serde_json::to_string(boat).map_err(|_| Error::InvalidParameter)?;
+    diesel::insert_into(boat_revisions::table)
+        .values(NewRevision {
+            boat_id: boat.id,
+            action: action.as_str().to_owned(),
+            actor: user.email.clone(),
+            snapshot,
+            created_at: Utc::now(),
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
+pub async fn get_history(
+    boat_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let history = history(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    Ok(reply::json(&history))
+}
+
+// changes to a boat, newest first. Who made them is only shown to the owner and admins
+fn history(
+    conn: &mut SqliteConnection,
+    boat_id: i32,
+    user: &User,
+) -> Result<Vec<RevisionEntry>, Error> {
+    let revisions: Vec<Revision> = boat_revisions::table
+        .filter(boat_revisions::boat_id.eq(boat_id))
+        .order(boat_revisions::id.asc())
+        .select(Revision::as_select())
+        .load(conn)?;
+    let current: Option<Boat> = boats::table
+        .find(boat_id)
+        .select(Boat::as_select())
+        .first(conn)
+        .optional()?;
+    if revisions.is_empty() && current.is_none() {
+        return Err(Error::NotFound);
+    }
+    // the owner of a purged boat is gone with it
+    let show_actor = check_owner(
+        current.as_ref().and_then(|boat| boat.owner_email.as_ref()),
+        user,
+    )
+    .is_ok();
+
+    // each revision holds the state before its change, so the state after it is the next
+    // revision's snapshot, or the current row for the latest one
+    let snapshots: Vec<Value> = revisions
+        .iter()
+        .map(|revision| serde_json::from_str(&revision.snapshot).unwrap_or(Value::Null))
+        .collect();
+    let current = current
+        .and_then(|boat| serde_json::to_value(boat).ok())
+        .unwrap_or(Value::Null);
+    let mut history: Vec<RevisionEntry> = revisions
+        .into_iter()
+        .enumerate()
+        .map(|(i, revision)| RevisionEntry {
+            id: revision.id,
+            boat_id: revision.boat_id,
+            changes: diff(&snapshots[i], snapshots.get(i + 1).unwrap_or(&current)),
+            action: revision.action,
+            actor: show_actor.then_some(revision.actor),
+            created_at: revision.created_at,
+        })
+        .collect();
+    history.reverse();
+    Ok(history)
+}
+
+pub async fn revert_boat(
+    boat_id: i32,
+    revision_id: i32,
+    if_match: Option<String>,
+    units: Units,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let boat = conn
+        .immediate_transaction(|conn| {
+            revert(conn, boat_id, revision_id, if_match.as_deref(), &user)
+        })
+        .map_err(reject::custom)?;
+    let version = boat.version;
+    Ok(label_units(
+        with_etag(reply::json(&boat.in_units(units)), version, units),
+        units,
+    ))
+}
+
+// puts a boat back to the state recorded in one of its revisions, to be run in a transaction
+fn revert(
+    conn: &mut SqliteConnection,
+    boat_id: i32,
+    revision_id: i32,
+    if_match: Option<&str>,
+    user: &User,
+) -> Result<Boat, Error> {
+    authorize_boat_owner(conn, boat_id, user)?;
+    let current: Boat = boats::table
+        .find(boat_id)
+        .select(Boat::as_select())
+        .first(conn)?;
+    check_if_match(if_match, current.version)?;
+    let snapshot: String = boat_revisions::table
+        .filter(boat_revisions::id.eq(revision_id))
+        .filter(boat_revisions::boat_id.eq(boat_id))
+        .select(boat_revisions::snapshot)
+        .first(conn)?;
+    let mut state: NewBoat =
+        serde_json::from_str(&snapshot).map_err(|_| Error::InvalidParameter)?;
+    // the rules may have changed since the snapshot was taken
+    state.validate()?;
+    record_revision(conn, &current, RevisionAction::Revert, user)?;
+    Ok(diesel::update(boats::table.find(boat_id))
+        .set((
+            BoatState::from(state),
+            boats::version.eq(boats::version + 1),
+            boats::updated_at.eq(Utc::now()),
+        ))
+        .returning(Boat::as_returning())
+        .get_result(conn)?)
+}
+
+// field-level differences between two serialized boats, either of which may be null
+fn diff(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| !BOOKKEEPING_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let from = before.get(field).cloned().unwrap_or(Value::Null);
+            let to = after.get(field).cloned().unwrap_or(Value::Null);
+            (from != to).then(|| FieldChange {
+                field: field.clone(),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{test_connection, test_user},
+        handlers::boat::{insert_boat, replace_boat},
+    };
+    use serde_json::json;
+
+    fn new_boat(name: &str, year: i32) -> NewBoat {
+        serde_json::from_value(json!({
+            "name": name,
+            "make": "Hallberg-Rassy",
+            "model": "340",
+            "year": year,
+        }))
+        .unwrap()
+    }
+
+    // a boat renamed twice, with the revisions of both renames
+    fn renamed_boat(conn: &mut SqliteConnection, owner: &User) -> (Boat, Vec<i32>) {
+        let boat = insert_boat(conn, new_boat("Aurora", 2010), owner).unwrap();
+        for name in ["Bora", "Calypso"] {
+            replace_boat(conn, boat.id, Some("*"), owner, |_| {
+                Ok(new_boat(name, 2010))
+            })
+            .unwrap();
+        }
+        let revisions = boat_revisions::table
+            .filter(boat_revisions::boat_id.eq(boat.id))
+            .order(boat_revisions::id.asc())
+            .select(boat_revisions::id)
+            .load(conn)
+            .unwrap();
+        (boat, revisions)
+    }
+
+    fn renames(history: &[RevisionEntry]) -> Vec<(Value, Value)> {
+        history
+            .iter()
+            .map(|entry| {
+                assert_eq!(entry.changes.len(), 1);
+                assert_eq!(entry.changes[0].field, "name");
+                (entry.changes[0].from.clone(), entry.changes[0].to.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn history_is_newest_first() {
+        let mut conn = test_connection();
+        let owner = test_user(&mut conn, "owner@example.com", false);
+        let (boat, _) = renamed_boat(&mut conn, &owner);
+
+        let history = history(&mut conn, boat.id, &owner).unwrap();
+        assert_eq!(
+            renames(&history),
+            [
+                (json!("Bora"), json!("Calypso")),
+                (json!("Aurora"), json!("Bora")),
+            ]
+        );
+        assert!(history[0].id > history[1].id);
+        assert_eq!(history[0].actor.as_deref(), Some("owner@example.com"));
+    }
+
+    #[test]
+    fn history_shows_editors_to_the_owner_and_admins_only() {
+        let mut conn = test_connection();
+        let owner = test_user(&mut conn, "owner@example.com", false);
+        let admin = test_user(&mut conn, "admin@example.com", true);
+        let other = test_user(&mut conn, "other@example.com", false);
+        let (boat, _) = renamed_boat(&mut conn, &owner);
+
+        for (user, shown) in [(&owner, true), (&admin, true), (&other, false)] {
+            let history = history(&mut conn, boat.id, user).unwrap();
+            assert_eq!(history.len(), 2);
+            assert!(history.iter().all(|entry| entry.actor.is_some() == shown));
+        }
+    }
+
+    #[test]
+    fn reverts_to_a_revision() {
+        let mut conn = test_connection();
+        let owner = test_user(&mut conn, "owner@example.com", false);
+        let (boat, revisions) = renamed_boat(&mut conn, &owner);
+
+        let reverted = revert(&mut conn, boat.id, revisions[0], Some("\"3\""), &owner).unwrap();
+        assert_eq!(reverted.name, "Aurora");
+        assert_eq!(reverted.version, 4);
+        let history = history(&mut conn, boat.id, &owner).unwrap();
+        assert_eq!(history[0].action, "revert");
+        assert_eq!(
+            renames(&history[..1]),
+            [(json!("Calypso"), json!("Aurora"))]
+        );
+    }
+
+    #[test]
+    fn revert_checks_preconditions_and_permissions() {
+        let mut conn = test_connection();
+        let owner = test_user(&mut conn, "owner@example.com", false);
+        let other = test_user(&mut conn, "other@example.com", false);
+        let (boat, revisions) = renamed_boat(&mut conn, &owner);
+
+        assert!(matches!(
+            revert(&mut conn, boat.id, revisions[0], Some("\"2\""), &owner),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            revert(&mut conn, boat.id, revisions[0], Some("*"), &other),
+            Err(Error::NoPermission)
+        ));
+        assert!(matches!(
+            revert(&mut conn, boat.id, revisions[0] + 100, Some("*"), &owner),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn revert_validates_the_snapshot() {
+        let mut conn = test_connection();
+        let owner = test_user(&mut conn, "owner@example.com", false);
+        let (boat, revisions) = renamed_boat(&mut conn, &owner);
+        // a snapshot from before the year rules were tightened
+        diesel::update(boat_revisions::table.find(revisions[0]))
+            .set(
+                boat_revisions::snapshot.eq(json!({
+                    "name": "Aurora",
+                    "make": "Hallberg-Rassy",
+                    "model": "340",
+                    "year": 1700,
+                })
+                .to_string()),
+            )
+            .execute(&mut conn)
+            .unwrap();
+
+        assert!(matches!(
+            revert(&mut conn, boat.id, revisions[0], Some("*"), &owner),
+            Err(Error::Validation(_))
+        ));
+        let current: Boat = boats::table
+            .find(boat.id)
+            .select(Boat::as_select())
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!((current.name.as_str(), current.version), ("Calypso", 3));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    db::SharedConnectionPool,
+    errors::Error,
+    handlers::helpers::{acquire_connection, authorize_boat_owner},
+    models::{
+        tag::{NewTag, Tag, TagQuery},
+        user::User,
+    },
+    schema::{boat_tags, boats, tags},
+};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper};
+use warp::{http::StatusCode, reject, reply};
+
+pub async fn get_all_tags(
+    query: TagQuery,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let mut tags = tags::table.into_boxed();
+    if let Some(category) = query.category {
+        tags = tags.filter(tags::category.eq(category));
+    }
+    let tags: Vec<Tag> = tags
+        .order(tags::name)
+        .select(Tag::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&tags))
+}
+
+pub async fn get_tag(
+    id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    let tag: Tag = tags::table
+        .find(id)
+        .select(Tag::as_select())
+        .first(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&tag))
+}
+
+// tags are shared by every boat, so only admins can change them
+pub async fn create_tag(
+    mut tag: NewTag,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !user.is_admin {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    tag.validate().map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    let tag: Tag = diesel::insert_into(tags::table)
+        .values(&tag)
+        .returning(Tag::as_returning())
+        .get_result(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::with_status(reply::json(&tag), StatusCode::CREATED))
+}
+
+pub async fn update_tag(
+    id: i32,
+    mut tag: NewTag,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !user.is_admin {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    tag.validate().map_err(reject::custom)?;
+    let mut conn = acquire_connection(&pool).await?;
+    let tag: Tag = diesel::update(tags::table.find(id))
+        .set(&tag)
+        .returning(Tag::as_returning())
+        .get_result(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&tag))
+}
+
+// the tag is removed from every boat carrying it
+pub async fn delete_tag(
+    id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !user.is_admin {
+        return Err(reject::custom(Error::NoPermission));
+    }
+    let mut conn = acquire_connection(&pool).await?;
+    let deleted = diesel::delete(tags::table.find(id))
+        .execute(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    if deleted == 0 {
+        return Err(reject::custom(Error::NotFound));
+    }
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+pub async fn get_boat_tags(
+    boat_id: i32,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    boats::table
+        .find(boat_id)
+        .filter(boats::deleted_at.is_null())
+        .select(boats::id)
+        .first::<i32>(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    let tags: Vec<Tag> = boat_tags::table
+        .inner_join(tags::table)
+        .filter(boat_tags::boat_id.eq(boat_id))
+        .order(tags::name)
+        .select(Tag::as_select())
+        .load(&mut conn)
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    Ok(reply::json(&tags))
+}
+
+// attaching a tag the boat already has is not an error
+pub async fn attach_tag(
+    boat_id: i32,
+    tag_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    conn.immediate_transaction::<(), Error, _>(|conn| {
+        authorize_boat_owner(conn, boat_id, &user)?;
+        tags::table
+            .find(tag_id)
+            .select(tags::id)
+            .first::<i32>(conn)
+            .optional()?
+            .ok_or(Error::NotFound)?;
+        diesel::insert_or_ignore_into(boat_tags::table)
+            .values((boat_tags::boat_id.eq(boat_id), boat_tags::tag_id.eq(tag_id)))
+            .execute(conn)?;
+        Ok(())
+    })
+    .map_err(reject::custom)?;
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
+
+pub async fn detach_tag(
+    boat_id: i32,
+    tag_id: i32,
+    user: User,
+    pool: SharedConnectionPool,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut conn = acquire_connection(&pool).await?;
+    authorize_boat_owner(&mut conn, boat_id, &user).map_err(reject::custom)?;
+    let deleted = diesel::delete(
+        boat_tags::table
+            .filter(boat_tags::boat_id.eq(boat_id))
+            .filter(boat_tags::tag_id.eq(tag_id)),
+    )
+    .execute(&mut conn)
+    .map_err(|e| reject::custom(Error::from(e)))?;
+    if deleted == 0 {
+        return Err(reject::custom(Error::NotFound));
+    }
+    Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT))
+}
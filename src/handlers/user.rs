@@ -20,7 +20,7 @@ pub async fn create_user(
             api_key: Some(api_key.to_string()),
         })
         .execute(&mut conn)
-        .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+        .map_err(|e| reject::custom(Error::from(e)))?;
     Ok(reply::with_status(
         reply::json(&format!("User created. Your API key is {}", &api_key)),
         StatusCode::CREATED,
@@ -38,10 +38,13 @@ pub async fn add_credit(
     let amount = amount
         .parse::<i32>()
         .map_err(|_| reject::custom(Error::InvalidParameter))?;
-    diesel::update(users::table.filter(users::api_key.eq(api_key)))
+    let updated = diesel::update(users::table.filter(users::api_key.eq(api_key)))
         .set(users::credit.eq(users::credit + amount))
         .execute(&mut conn)
-        .map_err(|_| reject::custom(Error::NotFound))?;
+        .map_err(|e| reject::custom(Error::from(e)))?;
+    if updated == 0 {
+        return Err(reject::custom(Error::NotFound));
+    }
     Ok(reply::with_status(
         reply::json(&String::from("Added credit")),
         StatusCode::CREATED,
@@ -0,0 +1,834 @@
+use crate::errors::Error;
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+pub const CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+const PRODID: &str = "-//rustic-api//boats//EN";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+const DATE_FORMAT: &str = "%Y%m%d";
+const MAX_LINE_OCTETS: usize = 75;
+// recurring events are expanded at most this many times
+const MAX_OCCURRENCES: usize = 1000;
+// largest INTERVAL of a recurring event, e.g. every 1000 days or years
+const MAX_INTERVAL: u32 = 1000;
+// periods of a recurring event looked at before giving up on it, once those that end long
+// before the expanded range are skipped
+const MAX_PERIODS: u32 = 10_000;
+
+// a single busy period, either read from or written to a calendar
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+pub fn render_calendar(name: &str, events: &[CalendarEvent]) -> String {
+    let stamp = Utc::now().format(DATE_TIME_FORMAT);
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:{}", PRODID),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+    for event in events {
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!("UID:{}", escape_text(&event.uid)));
+        lines.push(format!("DTSTAMP:{}Z", stamp));
+        lines.push(format!(
+            "DTSTART:{}Z",
+            event.starts_at.format(DATE_TIME_FORMAT)
+        ));
+        lines.push(format!("DTEND:{}Z", event.ends_at.format(DATE_TIME_FORMAT)));
+        if let Some(summary) = &event.summary {
+            lines.push(format!("SUMMARY:{}", escape_text(summary)));
+        }
+        lines.push(String::from("TRANSP:OPAQUE"));
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+// reads the busy VEVENTs of a calendar. recurring events are expanded up to `horizon`
+// past their first occurrence, and occurrences that ended before `since` are dropped
+pub fn parse_events(
+    ics: &str,
+    since: DateTime<Utc>,
+    horizon: DateTime<Utc>,
+) -> Result<Vec<CalendarEvent>, Error> {
+    let mut events = Vec::new();
+    let mut event: Option<EventProperties> = None;
+    // depth of components nested inside the current VEVENT, e.g. VALARM
+    let mut nested = 0;
+    let mut in_calendar = false;
+
+    for line in unfold_lines(ics) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = parse_property(&line)?;
+        match (
+            property.name.as_str(),
+            property.value.to_ascii_uppercase().as_str(),
+        ) {
+            ("BEGIN", "VCALENDAR") => in_calendar = true,
+            ("BEGIN", "VEVENT") if event.is_none() => event = Some(EventProperties::default()),
+            ("BEGIN", _) if event.is_some() => nested += 1,
+            ("END", "VEVENT") if nested == 0 => {
+                if let Some(properties) = event.take() {
+                    events.extend(properties.into_events(since, horizon)?);
+                }
+            }
+            ("END", _) if event.is_some() => nested -= 1,
+            _ if nested == 0 => {
+                if let Some(properties) = event.as_mut() {
+                    properties.set(property);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !in_calendar || event.is_some() {
+        return Err(Error::InvalidParameter);
+    }
+    Ok(events)
+}
+
+// content line, e.g. `DTSTART;TZID=Europe/Paris:20240603T120000`
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Default)]
+struct EventProperties {
+    uid: Option<String>,
+    summary: Option<String>,
+    dtstart: Option<Property>,
+    dtend: Option<Property>,
+    duration: Option<String>,
+    rrule: Option<String>,
+    exdates: Vec<Property>,
+    // cancelled and transparent (free) events do not block the boat
+    skip: bool,
+}
+
+impl EventProperties {
+    fn set(&mut self, property: Property) {
+        match property.name.as_str() {
+            "UID" => self.uid = Some(unescape_text(&property.value)),
+            "SUMMARY" => self.summary = Some(unescape_text(&property.value)),
+            "DTSTART" => self.dtstart = Some(property),
+            "DTEND" => self.dtend = Some(property),
+            "DURATION" => self.duration = Some(property.value),
+            "RRULE" => self.rrule = Some(property.value),
+            "EXDATE" => self.exdates.push(property),
+            "STATUS" => self.skip |= property.value.eq_ignore_ascii_case("CANCELLED"),
+            "TRANSP" => self.skip |= property.value.eq_ignore_ascii_case("TRANSPARENT"),
+            _ => {}
+        }
+    }
+
+    fn into_events(
+        self,
+        since: DateTime<Utc>,
+        horizon: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>, Error> {
+        if self.skip {
+            return Ok(Vec::new());
+        }
+        let dtstart = self.dtstart.as_ref().ok_or(Error::InvalidParameter)?;
+        let start = EventTime::parse(dtstart)?;
+        let starts_at = start.to_utc(start.local)?;
+        let ends_at = match (&self.dtend, &self.duration) {
+            (Some(dtend), _) => {
+                let end = EventTime::parse(dtend)?;
+                end.to_utc(end.local)?
+            }
+            (None, Some(duration)) => starts_at
+                .checked_add_signed(parse_duration(duration)?)
+                .ok_or(Error::InvalidParameter)?,
+            // all-day events without an end last for that day
+            (None, None) if start.all_day => starts_at
+                .checked_add_signed(Duration::days(1))
+                .ok_or(Error::InvalidParameter)?,
+            (None, None) => starts_at,
+        };
+        if ends_at < starts_at {
+            return Err(Error::InvalidParameter);
+        }
+        let length = ends_at - starts_at;
+        // zero-length events do not take up any time
+        if length.is_zero() {
+            return Ok(Vec::new());
+        }
+
+        let mut excluded = Vec::new();
+        for exdate in &self.exdates {
+            for value in exdate.value.split(',') {
+                let time = EventTime::parse(&Property {
+                    name: exdate.name.clone(),
+                    params: exdate.params.clone(),
+                    value: value.to_string(),
+                })?;
+                excluded.push(time.to_utc(time.local)?);
+            }
+        }
+
+        let occurrences = match &self.rrule {
+            Some(rrule) => {
+                let since = since
+                    .checked_sub_signed(length)
+                    .ok_or(Error::InvalidParameter)?;
+                RecurrenceRule::parse(rrule)?.expand(&start, since, horizon)?
+            }
+            None => vec![starts_at],
+        };
+        let uid = self.uid.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut events = Vec::new();
+        for starts_at in occurrences {
+            let ends_at = starts_at
+                .checked_add_signed(length)
+                .ok_or(Error::InvalidParameter)?;
+            if excluded.contains(&starts_at) || ends_at <= since {
+                continue;
+            }
+            events.push(CalendarEvent {
+                uid: uid.clone(),
+                summary: self.summary.clone(),
+                starts_at,
+                ends_at,
+            });
+        }
+        Ok(events)
+    }
+}
+
+// DTSTART/DTEND/EXDATE value kept in its own time zone, so that recurrences keep the same
+// wall-clock time across daylight saving changes
+struct EventTime {
+    local: NaiveDateTime,
+    zone: Option<Tz>,
+    all_day: bool,
+}
+
+impl EventTime {
+    fn parse(property: &Property) -> Result<Self, Error> {
+        let value = property.value.trim();
+        let all_day = property.param("VALUE") == Some("DATE") || value.len() == 8;
+        if all_day {
+            // all-day events are taken as whole UTC days
+            let date = NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .map_err(|_| Error::InvalidParameter)?;
+            return Ok(EventTime {
+                local: date.and_time(NaiveTime::MIN),
+                zone: None,
+                all_day,
+            });
+        }
+
+        let (value, utc) = match value.strip_suffix('Z') {
+            Some(value) => (value, true),
+            None => (value, false),
+        };
+        let local = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+            .map_err(|_| Error::InvalidParameter)?;
+        // floating times without a zone are taken as UTC
+        let zone = match (utc, property.param("TZID")) {
+            (false, Some(tzid)) => Some(
+                tzid.trim_start_matches('/')
+                    .parse::<Tz>()
+                    .map_err(|_| Error::InvalidParameter)?,
+            ),
+            _ => None,
+        };
+        Ok(EventTime {
+            local,
+            zone,
+            all_day,
+        })
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> Result<DateTime<Utc>, Error> {
+        let Some(zone) = self.zone else {
+            return Ok(local.and_utc());
+        };
+        match zone.from_local_datetime(&local) {
+            LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => {
+                Ok(time.with_timezone(&Utc))
+            }
+            // times skipped by a daylight saving change take the offset from before the change,
+            // which moves them forward past the gap
+            LocalResult::None => {
+                let before = local
+                    .checked_sub_signed(Duration::days(1))
+                    .ok_or(Error::InvalidParameter)?;
+                let offset = zone.offset_from_utc_datetime(&before).fix();
+                local
+                    .checked_sub_signed(Duration::seconds(offset.local_minus_utc().into()))
+                    .map(|time| time.and_utc())
+                    .ok_or(Error::InvalidParameter)
+            }
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// supports FREQ, INTERVAL, COUNT, UNTIL and plain weekdays in BYDAY for weekly rules
+struct RecurrenceRule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<String>,
+    weekdays: Vec<Weekday>,
+}
+
+impl RecurrenceRule {
+    fn parse(rule: &str) -> Result<Self, Error> {
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut weekdays = Vec::new();
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or(Error::InvalidParameter)?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(Error::InvalidParameter),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value.parse().map_err(|_| Error::InvalidParameter)?;
+                    if interval == 0 || interval > MAX_INTERVAL {
+                        return Err(Error::InvalidParameter);
+                    }
+                }
+                "COUNT" => count = Some(value.parse().map_err(|_| Error::InvalidParameter)?),
+                "UNTIL" => until = Some(value.to_string()),
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        weekdays.push(match day.to_ascii_uppercase().as_str() {
+                            "MO" => Weekday::Mon,
+                            "TU" => Weekday::Tue,
+                            "WE" => Weekday::Wed,
+                            "TH" => Weekday::Thu,
+                            "FR" => Weekday::Fri,
+                            "SA" => Weekday::Sat,
+                            "SU" => Weekday::Sun,
+                            _ => return Err(Error::InvalidParameter),
+                        });
+                    }
+                }
+                "WKST" => {}
+                _ => return Err(Error::InvalidParameter),
+            }
+        }
+
+        let frequency = frequency.ok_or(Error::InvalidParameter)?;
+        if !weekdays.is_empty() && frequency != Frequency::Weekly {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(RecurrenceRule {
+            frequency,
+            interval,
+            count,
+            until,
+            weekdays,
+        })
+    }
+
+    // start times of the occurrences starting after `since`, the first possible one being
+    // DTSTART itself. earlier occurrences still count towards COUNT
+    fn expand(
+        &self,
+        start: &EventTime,
+        since: DateTime<Utc>,
+        horizon: DateTime<Utc>,
+    ) -> Result<Vec<DateTime<Utc>>, Error> {
+        let until = match &self.until {
+            Some(until) => {
+                let until = EventTime::parse(&Property {
+                    name: String::from("UNTIL"),
+                    params: Vec::new(),
+                    value: until.clone(),
+                })?;
+                // a date bound includes the whole of that day
+                let bound = if until.all_day {
+                    Duration::days(1)
+                } else {
+                    Duration::seconds(1)
+                };
+                Some(
+                    until
+                        .local
+                        .and_utc()
+                        .checked_add_signed(bound)
+                        .ok_or(Error::InvalidParameter)?,
+                )
+            }
+            None => None,
+        };
+        let skipped = self.skipped_periods(start, since);
+        let mut occurrences = Vec::new();
+        // every skipped period has exactly one occurrence, or the rule has no COUNT
+        let mut count = skipped as usize;
+        for period in skipped.. {
+            if period - skipped >= MAX_PERIODS {
+                return Err(Error::InvalidParameter);
+            }
+            let step = period
+                .checked_mul(self.interval)
+                .ok_or(Error::InvalidParameter)?;
+            let candidates = match self.frequency {
+                Frequency::Daily => vec![shift(start.local, Duration::try_days(step.into()))?],
+                Frequency::Weekly if self.weekdays.is_empty() => {
+                    vec![shift(start.local, Duration::try_weeks(step.into()))?]
+                }
+                Frequency::Weekly => {
+                    let monday = shift(
+                        start.local,
+                        Duration::try_days(-i64::from(
+                            start.local.weekday().num_days_from_monday(),
+                        )),
+                    )?;
+                    let week_start = shift(monday, Duration::try_weeks(step.into()))?;
+                    let mut days = Vec::new();
+                    for day in &self.weekdays {
+                        let day = shift(
+                            week_start,
+                            Duration::try_days(day.num_days_from_monday().into()),
+                        )?;
+                        if day >= start.local {
+                            days.push(day);
+                        }
+                    }
+                    days.sort();
+                    days
+                }
+                // months and years without the start's day (e.g. the 31st) are skipped
+                Frequency::Monthly => {
+                    let months = start
+                        .local
+                        .month0()
+                        .checked_add(step)
+                        .ok_or(Error::InvalidParameter)?;
+                    let year = recurrence_year(start.local.year(), months / 12)?;
+                    NaiveDate::from_ymd_opt(year, months % 12 + 1, start.local.day())
+                        .map(|date| date.and_time(start.local.time()))
+                        .into_iter()
+                        .collect()
+                }
+                Frequency::Yearly => NaiveDate::from_ymd_opt(
+                    recurrence_year(start.local.year(), step)?,
+                    start.local.month(),
+                    start.local.day(),
+                )
+                .map(|date| date.and_time(start.local.time()))
+                .into_iter()
+                .collect(),
+            };
+
+            for candidate in candidates {
+                let candidate = start.to_utc(candidate)?;
+                if (candidate > horizon && count > 0)
+                    || until.is_some_and(|until| candidate >= until)
+                    || self.count.is_some_and(|limit| count >= limit)
+                {
+                    return Ok(occurrences);
+                }
+                count += 1;
+                if candidate > since {
+                    occurrences.push(candidate);
+                }
+                if occurrences.len() >= MAX_OCCURRENCES {
+                    return Ok(occurrences);
+                }
+            }
+        }
+        Ok(occurrences)
+    }
+
+    // periods that end before `since` and can be left out of the expansion without counting
+    // their occurrences. One period is kept as a margin for the time zone of the start
+    fn skipped_periods(&self, start: &EventTime, since: DateTime<Utc>) -> u32 {
+        let one_per_period = self.frequency == Frequency::Daily
+            || self.frequency == Frequency::Weekly && self.weekdays.is_empty();
+        if self.count.is_some() && !one_per_period {
+            return 0;
+        }
+        let since = since.naive_utc();
+        if since <= start.local {
+            return 0;
+        }
+        let days = (since - start.local).num_days();
+        let months = i64::from(since.year() - start.local.year()) * 12 + i64::from(since.month0())
+            - i64::from(start.local.month0());
+        let periods = match self.frequency {
+            Frequency::Daily => days,
+            Frequency::Weekly => days / 7,
+            Frequency::Monthly => months,
+            Frequency::Yearly => months / 12,
+        } / i64::from(self.interval);
+        u32::try_from(periods.saturating_sub(1).max(0)).unwrap_or(u32::MAX)
+    }
+}
+
+// moves a local time by a number of days or weeks, failing when either is out of range
+fn shift(time: NaiveDateTime, by: Option<Duration>) -> Result<NaiveDateTime, Error> {
+    by.and_then(|by| time.checked_add_signed(by))
+        .ok_or(Error::InvalidParameter)
+}
+
+// year `years` after `year`. recurrences that never reach a valid date run out of years here
+fn recurrence_year(year: i32, years: u32) -> Result<i32, Error> {
+    i32::try_from(years)
+        .ok()
+        .and_then(|years| year.checked_add(years))
+        .filter(|year| *year <= NaiveDate::MAX.year())
+        .ok_or(Error::InvalidParameter)
+}
+
+// DURATION value, e.g. `P1D`, `PT1H30M` or `P2W`
+fn parse_duration(value: &str) -> Result<Duration, Error> {
+    let value = value.trim().trim_start_matches('+');
+    let value = value.strip_prefix('P').ok_or(Error::InvalidParameter)?;
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            _ => {
+                let amount: i64 = number.parse().map_err(|_| Error::InvalidParameter)?;
+                number.clear();
+                let part = match (c, in_time) {
+                    ('W', false) => Duration::try_weeks(amount),
+                    ('D', false) => Duration::try_days(amount),
+                    ('H', true) => Duration::try_hours(amount),
+                    ('M', true) => Duration::try_minutes(amount),
+                    ('S', true) => Duration::try_seconds(amount),
+                    _ => return Err(Error::InvalidParameter),
+                };
+                duration = part
+                    .and_then(|part| duration.checked_add(&part))
+                    .ok_or(Error::InvalidParameter)?;
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(Error::InvalidParameter);
+    }
+    Ok(duration)
+}
+
+// joins content lines that were folded onto continuation lines starting with whitespace
+fn unfold_lines(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_property(line: &str) -> Result<Property, Error> {
+    // the value starts after the first colon outside of a quoted parameter value
+    let mut quoted = false;
+    let split = line
+        .char_indices()
+        .find(|(_, c)| {
+            if *c == '"' {
+                quoted = !quoted;
+            }
+            *c == ':' && !quoted
+        })
+        .map(|(index, _)| index)
+        .ok_or(Error::InvalidParameter)?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next().unwrap_or_default().trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Ok(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+// lines longer than 75 octets are split onto continuation lines, never inside a character
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn calendar(event: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:test\r\n{}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            event
+        )
+    }
+
+    fn starts(event: &str) -> Result<Vec<DateTime<Utc>>, Error> {
+        let since = utc("20240101T000000");
+        let horizon = utc("20250101T000000");
+        Ok(parse_events(&calendar(event), since, horizon)?
+            .into_iter()
+            .map(|event| event.starts_at)
+            .collect())
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("P2W").unwrap(), Duration::weeks(2));
+        assert_eq!(
+            parse_duration("P1DT1H30M15S").unwrap(),
+            Duration::days(1) + Duration::hours(1) + Duration::minutes(30) + Duration::seconds(15)
+        );
+        assert_eq!(parse_duration("+PT45M").unwrap(), Duration::minutes(45));
+        assert!(parse_duration("1D").is_err());
+        assert!(parse_duration("P1H").is_err());
+        assert!(parse_duration("PT1D").is_err());
+        assert!(parse_duration("P1").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_durations() {
+        assert!(parse_duration("P99999999999999W").is_err());
+        assert!(parse_duration("P9223372036854775807D").is_err());
+        assert!(parse_duration("P99999999999999999999S").is_err());
+        assert!(parse_duration("P15000000WT9223372036854775S").is_err());
+        assert!(starts("DTSTART:20240601T100000Z\r\nDURATION:P99999999999999W").is_err());
+    }
+
+    #[test]
+    fn parses_recurrence_rules() {
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;COUNT=4").unwrap();
+        assert!(rule.frequency == Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(4));
+        assert_eq!(rule.weekdays, vec![Weekday::Mon, Weekday::Fri]);
+
+        assert!(RecurrenceRule::parse("INTERVAL=2").is_err());
+        assert!(RecurrenceRule::parse("FREQ=HOURLY").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=0").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;BYDAY=MO").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;BYMONTH=1").is_err());
+    }
+
+    #[test]
+    fn rejects_large_intervals() {
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=1000").is_ok());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=1001").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;INTERVAL=4294967295").is_err());
+        assert!(
+            starts("DTSTART:20240601T100000Z\r\nDTEND:20240601T120000Z\r\nRRULE:FREQ=DAILY;INTERVAL=4294967295")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn expands_daily_events() {
+        let starts = starts(
+            "DTSTART:20240601T100000Z\r\nDTEND:20240601T120000Z\r\nRRULE:FREQ=DAILY;INTERVAL=2;COUNT=3",
+        )
+        .unwrap();
+        assert_eq!(
+            starts,
+            vec![
+                utc("20240601T100000"),
+                utc("20240603T100000"),
+                utc("20240605T100000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_periods_long_before_the_range() {
+        let daily =
+            starts("DTSTART:19000101T100000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=DAILY;INTERVAL=3")
+                .unwrap();
+        assert_eq!(daily[..2], [utc("20240102T100000"), utc("20240105T100000")]);
+        assert_eq!(daily.len(), 122);
+
+        // skipped periods still count towards COUNT
+        let counted =
+            starts("DTSTART:19000101T100000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=DAILY;COUNT=45292")
+                .unwrap();
+        assert_eq!(counted, [utc("20240101T100000"), utc("20240102T100000")]);
+    }
+
+    #[test]
+    fn gives_up_on_rules_with_too_many_periods() {
+        assert!(starts(
+            "DTSTART:00010101T100000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=MONTHLY;COUNT=100000"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn expands_weekly_events_on_weekdays() {
+        // 2024-06-05 is a wednesday
+        let starts = starts(
+            "DTSTART:20240605T090000Z\r\nDURATION:PT1H\r\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20240612",
+        )
+        .unwrap();
+        assert_eq!(
+            starts,
+            vec![
+                utc("20240605T090000"),
+                utc("20240610T090000"),
+                utc("20240612T090000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_months_without_the_start_day() {
+        let starts =
+            starts("DTSTART;VALUE=DATE:20240131\r\nRRULE:FREQ=MONTHLY;COUNT=3\r\nEXDATE;VALUE=DATE:20240331")
+                .unwrap();
+        assert_eq!(starts, vec![utc("20240131T000000"), utc("20240531T000000")]);
+    }
+
+    #[test]
+    fn keeps_wall_clock_time_across_daylight_saving() {
+        let starts = starts(
+            "DTSTART;TZID=Europe/Paris:20240325T120000\r\nDURATION:PT1H\r\nRRULE:FREQ=WEEKLY;COUNT=2",
+        )
+        .unwrap();
+        assert_eq!(starts, vec![utc("20240325T110000"), utc("20240401T100000")]);
+    }
+
+    #[test]
+    fn moves_times_in_a_daylight_saving_gap_forward() {
+        // 02:30 does not exist in Paris on 2024-03-31, it is read as 03:30 summer time
+        let starts = starts("DTSTART;TZID=Europe/Paris:20240331T023000\r\nDURATION:PT1H").unwrap();
+        assert_eq!(starts, vec![utc("20240331T013000")]);
+    }
+
+    #[test]
+    fn drops_cancelled_and_past_events() {
+        assert!(
+            starts("DTSTART:20240601T100000Z\r\nDURATION:PT1H\r\nSTATUS:CANCELLED")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(starts("DTSTART:20230601T100000Z\r\nDURATION:PT1H")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn escapes_and_unescapes_text() {
+        let text = "Refit; hull, deck\\rigging\nday 2";
+        let escaped = escape_text(text);
+        assert_eq!(escaped, r"Refit\; hull\, deck\\rigging\nday 2");
+        assert_eq!(unescape_text(&escaped), text);
+    }
+
+    #[test]
+    fn folds_and_unfolds_long_lines() {
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(unfold_lines(&folded), vec![line]);
+    }
+
+    #[test]
+    fn splits_properties_outside_quoted_parameters() {
+        let property = parse_property("DTSTART;TZID=\"America/New:York\":20240603T120000").unwrap();
+        assert_eq!(property.name, "DTSTART");
+        assert_eq!(property.param("TZID"), Some("America/New:York"));
+        assert_eq!(property.value, "20240603T120000");
+    }
+}
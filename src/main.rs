@@ -1,13 +1,25 @@
+mod conditional;
 mod config;
 mod credit;
 mod db;
 mod errors;
+mod export;
+mod geo;
 mod handlers;
+mod ical;
+mod media;
 mod models;
+mod pagination;
+mod patch;
+mod pricing;
 mod rate_limiting;
 mod responses;
 mod routes;
 mod schema;
+mod search;
+mod storage;
+mod units;
+mod validation;
 
 use std::num::NonZeroU32;
 use std::sync::Arc;
@@ -17,6 +29,7 @@ use crate::{
     db::{ConnectionPool, SharedConnectionPool},
     errors::handle_rejection,
     rate_limiting::QUOTA_PER_SECOND,
+    storage::{LocalStorage, SharedStorage},
 };
 use anyhow::Result;
 use governor::{Quota, RateLimiter};
@@ -34,6 +47,7 @@ async fn main() -> Result<()> {
     let rate_limiter = Arc::new(Mutex::new(RateLimiter::keyed(Quota::per_second(
         NonZeroU32::new(QUOTA_PER_SECOND).unwrap(),
     ))));
+    let storage: SharedStorage = Arc::new(LocalStorage::new(config.get_string("storage.path")?));
 
     // set up logger
     env_logger::Builder::from_default_env()
@@ -42,7 +56,7 @@ async fn main() -> Result<()> {
 
     // serve API
     warp::serve(
-        routes::all_routes(pool, rate_limiter)
+        routes::all_routes(pool, rate_limiter, storage)
             .with(warp::cors().allow_any_origin().allow_credentials(true))
             .recover(handle_rejection),
     )
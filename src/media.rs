@@ -0,0 +1,115 @@
+use crate::errors::Error;
+use image::{
+    codecs::jpeg::JpegEncoder, DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits,
+};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+
+pub const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+pub const THUMBNAIL_SIZE: u32 = 320;
+
+// larger images are rejected before decoding so that small files can't expand into huge bitmaps
+const MAX_IMAGE_DIMENSION: u32 = 12_000;
+const JPEG_QUALITY: u8 = 90;
+const THUMBNAIL_QUALITY: u8 = 80;
+
+pub struct ProcessedMedia {
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub data: Vec<u8>,
+    pub thumbnail: Option<Vec<u8>>,
+    pub checksum: String,
+}
+
+enum Kind {
+    Image(ImageFormat),
+    Pdf,
+}
+
+// the declared content type of an upload is not trusted, the type is taken from the file itself
+fn sniff(data: &[u8]) -> Option<Kind> {
+    if data.starts_with(b"%PDF-") {
+        return Some(Kind::Pdf);
+    }
+    match image::guess_format(data).ok()? {
+        format @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP) => {
+            Some(Kind::Image(format))
+        }
+        _ => None,
+    }
+}
+
+pub fn process(data: Vec<u8>) -> Result<ProcessedMedia, Error> {
+    if data.len() as u64 > MAX_FILE_SIZE {
+        return Err(Error::PayloadTooLarge);
+    }
+    match sniff(&data).ok_or(Error::UnsupportedMediaType)? {
+        Kind::Pdf => Ok(ProcessedMedia {
+            content_type: "application/pdf",
+            extension: "pdf",
+            checksum: checksum(&data),
+            data,
+            thumbnail: None,
+        }),
+        Kind::Image(format) => {
+            let image = decode(&data, format)?;
+            // re-encoding from the decoded pixels drops EXIF and any other embedded metadata
+            let data = encode(&image, format, JPEG_QUALITY)?;
+            let thumbnail = encode(
+                &image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE),
+                ImageFormat::Jpeg,
+                THUMBNAIL_QUALITY,
+            )?;
+            let (content_type, extension) = match format {
+                ImageFormat::Png => ("image/png", "png"),
+                ImageFormat::WebP => ("image/webp", "webp"),
+                _ => ("image/jpeg", "jpg"),
+            };
+            Ok(ProcessedMedia {
+                content_type,
+                extension,
+                checksum: checksum(&data),
+                data,
+                thumbnail: Some(thumbnail),
+            })
+        }
+    }
+}
+
+fn decode(data: &[u8], format: ImageFormat) -> Result<DynamicImage, Error> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|_| Error::UnsupportedMediaType)?;
+    // the orientation is only stored in EXIF, so it has to be applied to the pixels before the
+    // metadata is dropped
+    let orientation = decoder
+        .orientation()
+        .map_err(|_| Error::UnsupportedMediaType)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|_| Error::UnsupportedMediaType)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>, Error> {
+    let mut buffer = Cursor::new(Vec::new());
+    let result = match format {
+        // JPEG has no alpha channel
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut buffer, quality)
+            .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8())),
+        _ => image.write_to(&mut buffer, format),
+    };
+    result.map_err(|_| Error::StorageFailed)?;
+    Ok(buffer.into_inner())
+}
+
+fn checksum(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Interval,
+    Day,
+}
+
+#[derive(Deserialize)]
+pub struct AvailabilityQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub granularity: Option<Granularity>,
+}
+
+// half-open time interval [starts_at, ends_at)
+#[derive(Serialize, Clone, Copy)]
+pub struct Interval {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DayStatus {
+    Free,
+    Partial,
+    Busy,
+}
+
+#[derive(Serialize)]
+pub struct Availability {
+    pub boat_id: i32,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free: Option<Vec<Interval>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub busy: Option<Vec<Interval>>,
+    // keyed by UTC date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<BTreeMap<NaiveDate, DayStatus>>,
+}
@@ -0,0 +1,50 @@
+use crate::{
+    models::boat::{Boat, NewBoat},
+    validation::FieldError,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct BatchQuery {
+    // roll back every operation if any of them fails
+    pub atomic: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperation {
+    Create {
+        boat: NewBoat,
+    },
+    // replaces every editable field, like PUT
+    Update {
+        id: i32,
+        boat: NewBoat,
+        if_match: Option<String>,
+    },
+    Delete {
+        id: i32,
+        if_match: Option<String>,
+    },
+}
+
+// outcome of one operation, `status` is the HTTP status the operation would have on its own
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boat: Option<Boat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Serialize)]
+pub struct BatchReport {
+    pub atomic: bool,
+    // false when an atomic batch was rolled back
+    pub committed: bool,
+    pub results: Vec<BatchResult>,
+}
@@ -0,0 +1,29 @@
+use crate::schema::blackouts;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+// period in which a boat is blocked outside of the booking system, e.g. imported from
+// the owner's calendar
+#[derive(Deserialize, Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = blackouts)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Blackout {
+    pub id: i32,
+    pub boat_id: i32,
+    pub uid: String,
+    pub summary: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = blackouts)]
+pub struct NewBlackout {
+    pub boat_id: i32,
+    pub uid: String,
+    pub summary: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
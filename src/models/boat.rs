@@ -1,9 +1,50 @@
-use crate::schema::boats;
+use crate::{
+    errors::Error,
+    models::{
+        marina::{BoundingBox, GeoPoint},
+        tag::TagMatch,
+    },
+    schema::boats,
+    units::{Quantity, Units},
+    validation::{Validator, INVALID_VALUE, OUT_OF_RANGE},
+};
+use chrono::{DateTime, Datelike, Utc};
 use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable, Text};
 use diesel::sqlite::Sqlite;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Clone, Queryable, Selectable)]
+pub const MIN_YEAR: i32 = 1800;
+pub const MAX_NAME_LENGTH: usize = 100;
+// dimensions in metres
+pub const MIN_DIMENSION: f32 = 0.5;
+pub const MAX_LENGTH: f32 = 200.0;
+pub const MAX_BEAM: f32 = 50.0;
+pub const MIN_DRAFT: f32 = 0.1;
+pub const MAX_DRAFT: f32 = 25.0;
+pub const MAX_ENGINE_COUNT: i32 = 8;
+// per engine, in kilowatts
+pub const MAX_ENGINE_POWER: f32 = 10_000.0;
+// in litres
+pub const MAX_TANK_CAPACITY: f32 = 500_000.0;
+pub const MAX_BERTHS: i32 = 200;
+pub const MAX_CABINS: i32 = 100;
+pub const MAX_PASSENGERS: i32 = 1000;
+
+// allowed values of the text specifications
+pub const HULL_TYPES: &[&str] = &["monohull", "catamaran", "trimaran", "pontoon", "rib"];
+pub const HULL_MATERIALS: &[&str] = &[
+    "fiberglass",
+    "aluminium",
+    "steel",
+    "wood",
+    "composite",
+    "inflatable",
+];
+pub const PROPULSION_TYPES: &[&str] = &["sail", "outboard", "inboard", "sterndrive", "jet", "pod"];
+pub const FUEL_TYPES: &[&str] = &["petrol", "diesel", "electric", "hybrid"];
+
+#[derive(Deserialize, Serialize, Clone, Queryable, QueryableByName, Selectable)]
 #[diesel(table_name = boats)]
 #[diesel(check_for_backend(Sqlite))]
 pub struct Boat {
@@ -15,6 +56,31 @@ pub struct Boat {
     pub length: Option<f32>,
     pub beam: Option<f32>,
     pub is_available: i32,
+    pub marina_id: Option<i32>,
+    // only used for permission checks, never sent to clients
+    #[serde(skip_serializing)]
+    pub owner_email: Option<String>,
+    // set while the boat is in the trash
+    pub deleted_at: Option<DateTime<Utc>>,
+    // bumped on every write, sent as the ETag of the boat
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub hull_type: Option<String>,
+    pub hull_material: Option<String>,
+    pub propulsion: Option<String>,
+    pub engine_count: Option<i32>,
+    pub engine_power: Option<f32>,
+    pub fuel_type: Option<String>,
+    pub fuel_capacity: Option<f32>,
+    pub water_capacity: Option<f32>,
+    pub berths: Option<i32>,
+    pub cabins: Option<i32>,
+    pub max_passengers: Option<i32>,
+    pub draft: Option<f32>,
+    // average of the reviews, kept up to date when reviews are written
+    pub rating: Option<f64>,
+    pub review_count: i32,
 }
 
 #[derive(Deserialize, Insertable)]
@@ -27,15 +93,484 @@ pub struct NewBoat {
     pub length: Option<f32>,
     pub beam: Option<f32>,
     pub is_available: Option<i32>,
+    pub marina_id: Option<i32>,
+    pub hull_type: Option<String>,
+    pub hull_material: Option<String>,
+    pub propulsion: Option<String>,
+    pub engine_count: Option<i32>,
+    pub engine_power: Option<f32>,
+    pub fuel_type: Option<String>,
+    pub fuel_capacity: Option<f32>,
+    pub water_capacity: Option<f32>,
+    pub berths: Option<i32>,
+    pub cabins: Option<i32>,
+    pub max_passengers: Option<i32>,
+    pub draft: Option<f32>,
+    // set from the API key of the creating user
+    #[serde(skip_deserializing)]
+    pub owner_email: Option<String>,
+}
+
+impl NewBoat {
+    // trims the text fields in place, then checks every field
+    pub fn validate(&mut self) -> Result<(), Error> {
+        trim(&mut self.name);
+        trim(&mut self.make);
+        trim(&mut self.model);
+        let mut validator = Validator::default();
+        validator.text("name", &self.name, MAX_NAME_LENGTH);
+        validator.text("make", &self.make, MAX_NAME_LENGTH);
+        validator.text("model", &self.model, MAX_NAME_LENGTH);
+        validator.range("year", self.year, MIN_YEAR, max_year());
+        validate_dimensions(&mut validator, self.length, self.beam);
+        if let Some(is_available) = self.is_available {
+            validator.one_of("is_available", is_available, &[0, 1]);
+        }
+        validate_specs(&mut validator, self);
+        validator.finish()
+    }
+}
+
+// editable columns of a boat, written as a whole so that missing optional values clear the
+// column
+#[derive(Deserialize, Serialize, AsChangeset)]
+#[diesel(table_name = boats)]
+#[diesel(treat_none_as_null = true)]
+pub struct BoatState {
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    pub year: i32,
+    pub length: Option<f32>,
+    pub beam: Option<f32>,
+    pub is_available: i32,
+    pub marina_id: Option<i32>,
+    pub hull_type: Option<String>,
+    pub hull_material: Option<String>,
+    pub propulsion: Option<String>,
+    pub engine_count: Option<i32>,
+    pub engine_power: Option<f32>,
+    pub fuel_type: Option<String>,
+    pub fuel_capacity: Option<f32>,
+    pub water_capacity: Option<f32>,
+    pub berths: Option<i32>,
+    pub cabins: Option<i32>,
+    pub max_passengers: Option<i32>,
+    pub draft: Option<f32>,
+}
+
+impl From<&Boat> for BoatState {
+    fn from(boat: &Boat) -> Self {
+        BoatState {
+            name: boat.name.clone(),
+            make: boat.make.clone(),
+            model: boat.model.clone(),
+            year: boat.year,
+            length: boat.length,
+            beam: boat.beam,
+            is_available: boat.is_available,
+            marina_id: boat.marina_id,
+            hull_type: boat.hull_type.clone(),
+            hull_material: boat.hull_material.clone(),
+            propulsion: boat.propulsion.clone(),
+            engine_count: boat.engine_count,
+            engine_power: boat.engine_power,
+            fuel_type: boat.fuel_type.clone(),
+            fuel_capacity: boat.fuel_capacity,
+            water_capacity: boat.water_capacity,
+            berths: boat.berths,
+            cabins: boat.cabins,
+            max_passengers: boat.max_passengers,
+            draft: boat.draft,
+        }
+    }
+}
+
+impl From<NewBoat> for BoatState {
+    fn from(boat: NewBoat) -> Self {
+        BoatState {
+            name: boat.name,
+            make: boat.make,
+            model: boat.model,
+            year: boat.year,
+            length: boat.length,
+            beam: boat.beam,
+            is_available: boat.is_available.unwrap_or(1),
+            marina_id: boat.marina_id,
+            hull_type: boat.hull_type,
+            hull_material: boat.hull_material,
+            propulsion: boat.propulsion,
+            engine_count: boat.engine_count,
+            engine_power: boat.engine_power,
+            fuel_type: boat.fuel_type,
+            fuel_capacity: boat.fuel_capacity,
+            water_capacity: boat.water_capacity,
+            berths: boat.berths,
+            cabins: boat.cabins,
+            max_passengers: boat.max_passengers,
+            draft: boat.draft,
+        }
+    }
+}
+
+impl NewBoat {
+    // converts dimensions sent in `units` to the stored metric ones
+    pub fn into_metric(self, units: Units) -> Self {
+        let convert =
+            |quantity, value: Option<f32>| value.map(|value| units.to_metric(quantity, value));
+        NewBoat {
+            length: convert(Quantity::Length, self.length),
+            beam: convert(Quantity::Length, self.beam),
+            draft: convert(Quantity::Length, self.draft),
+            engine_power: convert(Quantity::Power, self.engine_power),
+            fuel_capacity: convert(Quantity::Volume, self.fuel_capacity),
+            water_capacity: convert(Quantity::Volume, self.water_capacity),
+            ..self
+        }
+    }
+}
+
+impl NewBoat {
+    // same as `into_metric` for an edited copy of `current` as expressed in `units`. Values
+    // the client left as they were keep their stored value rather than going through the
+    // rounding of a conversion
+    pub fn into_metric_from(self, units: Units, current: &Boat) -> Self {
+        let convert = |quantity, value: Option<f32>, stored: Option<f32>| {
+            if value == stored.map(|stored| units.express(quantity, stored)) {
+                stored
+            } else {
+                value.map(|value| units.to_metric(quantity, value))
+            }
+        };
+        NewBoat {
+            length: convert(Quantity::Length, self.length, current.length),
+            beam: convert(Quantity::Length, self.beam, current.beam),
+            draft: convert(Quantity::Length, self.draft, current.draft),
+            engine_power: convert(Quantity::Power, self.engine_power, current.engine_power),
+            fuel_capacity: convert(Quantity::Volume, self.fuel_capacity, current.fuel_capacity),
+            water_capacity: convert(
+                Quantity::Volume,
+                self.water_capacity,
+                current.water_capacity,
+            ),
+            ..self
+        }
+    }
+}
+
+impl Boat {
+    // converts the stored dimensions for a response in `units`
+    pub fn in_units(self, units: Units) -> Self {
+        let convert =
+            |quantity, value: Option<f32>| value.map(|value| units.express(quantity, value));
+        Boat {
+            length: convert(Quantity::Length, self.length),
+            beam: convert(Quantity::Length, self.beam),
+            draft: convert(Quantity::Length, self.draft),
+            engine_power: convert(Quantity::Power, self.engine_power),
+            fuel_capacity: convert(Quantity::Volume, self.fuel_capacity),
+            water_capacity: convert(Quantity::Volume, self.water_capacity),
+            ..self
+        }
+    }
+}
+
+fn trim(value: &mut String) {
+    let trimmed = value.trim();
+    if trimmed.len() != value.len() {
+        *value = trimmed.to_owned();
+    }
+}
+
+// boats can be ordered up to a year ahead
+fn max_year() -> i32 {
+    Utc::now().year() + 1
+}
+
+fn validate_dimensions(validator: &mut Validator, length: Option<f32>, beam: Option<f32>) {
+    if let Some(length) = length {
+        validator.range("length", length, MIN_DIMENSION, MAX_LENGTH);
+    }
+    if let Some(beam) = beam {
+        validator.range("beam", beam, MIN_DIMENSION, MAX_BEAM);
+    }
+    if let (Some(length), Some(beam)) = (length, beam) {
+        if beam > length {
+            validator.error(
+                "beam",
+                OUT_OF_RANGE,
+                String::from("beam must not exceed length"),
+            );
+        }
+    }
+}
+
+fn validate_specs(validator: &mut Validator, boat: &NewBoat) {
+    for (field, value, allowed) in [
+        ("hull_type", &boat.hull_type, HULL_TYPES),
+        ("hull_material", &boat.hull_material, HULL_MATERIALS),
+        ("propulsion", &boat.propulsion, PROPULSION_TYPES),
+        ("fuel_type", &boat.fuel_type, FUEL_TYPES),
+    ] {
+        if let Some(value) = value {
+            validator.one_of(field, value.as_str(), allowed);
+        }
+    }
+    if let Some(engine_count) = boat.engine_count {
+        validator.range("engine_count", engine_count, 0, MAX_ENGINE_COUNT);
+    }
+    if let Some(engine_power) = boat.engine_power {
+        validator.range("engine_power", engine_power, 1.0, MAX_ENGINE_POWER);
+        if boat.engine_count == Some(0) {
+            validator.error(
+                "engine_power",
+                INVALID_VALUE,
+                String::from("engine_power requires at least one engine"),
+            );
+        }
+    }
+    if let Some(fuel_capacity) = boat.fuel_capacity {
+        validator.range("fuel_capacity", fuel_capacity, 0.0, MAX_TANK_CAPACITY);
+    }
+    if let Some(water_capacity) = boat.water_capacity {
+        validator.range("water_capacity", water_capacity, 0.0, MAX_TANK_CAPACITY);
+    }
+    if let Some(berths) = boat.berths {
+        validator.range("berths", berths, 0, MAX_BERTHS);
+    }
+    if let Some(cabins) = boat.cabins {
+        validator.range("cabins", cabins, 0, MAX_CABINS);
+    }
+    if let Some(max_passengers) = boat.max_passengers {
+        validator.range("max_passengers", max_passengers, 1, MAX_PASSENGERS);
+    }
+    if let Some(draft) = boat.draft {
+        validator.range("draft", draft, MIN_DRAFT, MAX_DRAFT);
+    }
 }
 
 #[derive(Deserialize)]
-pub struct UpdateBoat {
-    pub name: Option<String>,
+pub struct BoatQuery {
     pub make: Option<String>,
     pub model: Option<String>,
-    pub year: Option<i32>,
-    pub length: Option<f32>,
-    pub beam: Option<f32>,
+    pub year_min: Option<i32>,
+    pub year_max: Option<i32>,
+    pub length_min: Option<f32>,
+    pub length_max: Option<f32>,
+    pub beam_max: Option<f32>,
+    pub hull_type: Option<String>,
+    pub hull_material: Option<String>,
+    pub propulsion: Option<String>,
+    pub fuel_type: Option<String>,
+    pub engine_count_min: Option<i32>,
+    // per engine, in kilowatts
+    pub engine_power_min: Option<f32>,
+    pub berths_min: Option<i32>,
+    pub cabins_min: Option<i32>,
+    // boats that take at least this many passengers
+    pub passengers_min: Option<i32>,
+    pub draft_max: Option<f32>,
+    pub min_rating: Option<f64>,
+    // comma separated tag names, e.g. tag=sailboat,pet-friendly
+    pub tag: Option<String>,
+    pub tag_match: Option<TagMatch>,
+    // also matches boats in a maintenance window as unavailable
     pub is_available: Option<i32>,
+    // only boats with no bookings, blackouts or maintenance overlapping this window, both bounds
+    // are required
+    pub available_from: Option<DateTime<Utc>>,
+    pub available_to: Option<DateTime<Utc>>,
+    // boats based within `radius_km` of a point, closest first
+    pub near: Option<GeoPoint>,
+    pub radius_km: Option<f64>,
+    // boats based inside a map area
+    pub bbox: Option<BoundingBox>,
+    pub sort: Option<BoatSort>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub cursor: Option<String>,
+    // export every matching boat as an attachment instead of returning a page
+    pub download: Option<bool>,
+}
+
+impl BoatQuery {
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .tag
+            .as_deref()
+            .map(|tag| tag.split(',').map(|name| name.trim().to_owned()).collect())
+            .unwrap_or_default();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    // converts the dimension bounds sent in `units` to metric ones
+    pub fn into_metric(self, units: Units) -> Self {
+        let convert =
+            |quantity, value: Option<f32>| value.map(|value| units.to_metric(quantity, value));
+        BoatQuery {
+            length_min: convert(Quantity::Length, self.length_min),
+            length_max: convert(Quantity::Length, self.length_max),
+            beam_max: convert(Quantity::Length, self.beam_max),
+            draft_max: convert(Quantity::Length, self.draft_max),
+            engine_power_min: convert(Quantity::Power, self.engine_power_min),
+            ..self
+        }
+    }
+}
+
+// listing entry for location searches
+#[derive(Serialize)]
+pub struct BoatListing {
+    #[serde(flatten)]
+    pub boat: Boat,
+    pub distance_km: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct BoatSearchQuery {
+    pub q: String,
+    pub highlight: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Serialize, QueryableByName)]
+pub struct BoatSearchResult {
+    #[serde(flatten)]
+    #[diesel(embed)]
+    pub boat: Boat,
+    // matching text with the search terms wrapped in highlight marks, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[diesel(sql_type = Nullable<Text>)]
+    pub snippet: Option<String>,
+}
+
+#[derive(QueryableByName)]
+pub struct BoatSearchCount {
+    #[diesel(sql_type = BigInt)]
+    pub count: i64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortField {
+    Id,
+    Name,
+    Make,
+    Model,
+    Year,
+    Length,
+    Beam,
+    Rating,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn reversed(self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+}
+
+// parsed from `field` or `field:asc|desc`, e.g. sort=year:desc
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct BoatSort {
+    pub field: SortField,
+    pub direction: SortDirection,
+}
+
+impl TryFrom<String> for BoatSort {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (field, direction) = value.split_once(':').unwrap_or((&value, "asc"));
+        let field = match field {
+            "id" => SortField::Id,
+            "name" => SortField::Name,
+            "make" => SortField::Make,
+            "model" => SortField::Model,
+            "year" => SortField::Year,
+            "length" => SortField::Length,
+            "beam" => SortField::Beam,
+            "rating" => SortField::Rating,
+            _ => return Err(format!("Unknown sort field: {}", field)),
+        };
+        let direction = match direction {
+            "asc" => SortDirection::Asc,
+            "desc" => SortDirection::Desc,
+            _ => return Err(format!("Unknown sort direction: {}", direction)),
+        };
+        Ok(BoatSort { field, direction })
+    }
+}
+
+impl From<BoatSort> for String {
+    fn from(sort: BoatSort) -> Self {
+        let field = match sort.field {
+            SortField::Id => "id",
+            SortField::Name => "name",
+            SortField::Make => "make",
+            SortField::Model => "model",
+            SortField::Year => "year",
+            SortField::Length => "length",
+            SortField::Beam => "beam",
+            SortField::Rating => "rating",
+        };
+        let direction = match sort.direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        format!("{}:{}", field, direction)
+    }
+}
+
+impl Default for BoatSort {
+    fn default() -> Self {
+        BoatSort {
+            field: SortField::Id,
+            direction: SortDirection::Asc,
+        }
+    }
+}
+
+// value of the sort column for the boat a cursor points at
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub enum SortKey {
+    Integer(i32),
+    Float(Option<f32>),
+    Double(Option<f64>),
+    Text(String),
+}
+
+// keyset position in a listing: rows strictly after (or before) `(key, id)` in `sort` order
+#[derive(Deserialize, Serialize)]
+pub struct BoatCursor {
+    pub sort: BoatSort,
+    pub key: SortKey,
+    pub id: i32,
+    pub before: bool,
+}
+
+impl Boat {
+    pub fn sort_key(&self, field: SortField) -> SortKey {
+        match field {
+            SortField::Id => SortKey::Integer(self.id),
+            SortField::Name => SortKey::Text(self.name.clone()),
+            SortField::Make => SortKey::Text(self.make.clone()),
+            SortField::Model => SortKey::Text(self.model.clone()),
+            SortField::Year => SortKey::Integer(self.year),
+            SortField::Length => SortKey::Float(self.length),
+            SortField::Beam => SortKey::Float(self.beam),
+            SortField::Rating => SortKey::Double(self.rating),
+        }
+    }
 }
@@ -0,0 +1,52 @@
+use crate::schema::bookings;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = bookings)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Booking {
+    pub id: i32,
+    pub boat_id: i32,
+    pub user_email: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+// a booking as listed for a boat, `user_email` is left out for other users
+#[derive(Serialize)]
+pub struct BookingListing {
+    pub id: i32,
+    pub boat_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_email: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl BookingListing {
+    pub fn new(booking: Booking, show_email: bool) -> Self {
+        BookingListing {
+            id: booking.id,
+            boat_id: booking.boat_id,
+            user_email: show_email.then_some(booking.user_email),
+            starts_at: booking.starts_at,
+            ends_at: booking.ends_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewBooking {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+// optional time window, bookings overlapping it are returned
+#[derive(Deserialize)]
+pub struct BookingQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
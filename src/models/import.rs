@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    // validate and report without writing anything
+    pub dry_run: Option<bool>,
+    // what to do with rows whose name is already taken, such rows are errors if unset
+    pub on_conflict: Option<OnConflict>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    Skip,
+    Update,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RowStatus {
+    Inserted,
+    Updated,
+    Skipped,
+    Error,
+}
+
+#[derive(Serialize)]
+pub struct ImportRow {
+    pub line: u64,
+    pub status: RowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub errors: usize,
+    // why the import stopped before the end of the upload. Rows up to the failed batch are
+    // written, the failed batch is reported as errors and later rows are left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aborted: Option<String>,
+    pub rows: Vec<ImportRow>,
+}
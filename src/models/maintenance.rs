@@ -0,0 +1,229 @@
+use crate::{
+    errors::Error,
+    schema::{maintenance_intervals, maintenance_records, maintenance_windows},
+    validation::{Validator, INVALID_VALUE, OUT_OF_RANGE, REQUIRED},
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Float, Integer, Nullable, Text, TimestamptzSqlite};
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_SERVICE_TYPE_LENGTH: usize = 50;
+pub const MAX_NOTES_LENGTH: usize = 2000;
+pub const MAX_ENGINE_HOURS: f32 = 100_000.0;
+pub const MAX_INTERVAL_DAYS: i32 = 3650;
+
+#[derive(Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = maintenance_records)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct MaintenanceRecord {
+    pub id: i32,
+    pub boat_id: i32,
+    pub service_type: String,
+    pub notes: Option<String>,
+    // in minor units of `currency`
+    pub cost: Option<i64>,
+    pub currency: Option<String>,
+    // engine hour meter reading at the time of the service
+    pub engine_hours: Option<f32>,
+    pub performed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = maintenance_records)]
+pub struct NewMaintenanceRecord {
+    #[serde(skip_deserializing)]
+    pub boat_id: i32,
+    pub service_type: String,
+    pub notes: Option<String>,
+    pub cost: Option<i64>,
+    pub currency: Option<String>,
+    pub engine_hours: Option<f32>,
+    pub performed_at: DateTime<Utc>,
+}
+
+impl NewMaintenanceRecord {
+    pub fn validate(&mut self) -> Result<(), Error> {
+        let mut validator = Validator::default();
+        validate_service(&mut validator, &mut self.service_type, &mut self.notes);
+        match (self.cost, &self.currency) {
+            (Some(cost), Some(currency)) => {
+                if cost < 0 {
+                    validator.error(
+                        "cost",
+                        OUT_OF_RANGE,
+                        String::from("cost must not be negative"),
+                    );
+                }
+                if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+                    validator.error(
+                        "currency",
+                        INVALID_VALUE,
+                        String::from("currency must be a three letter ISO 4217 code"),
+                    );
+                }
+            }
+            (Some(_), None) => validator.error(
+                "currency",
+                REQUIRED,
+                String::from("currency is required with a cost"),
+            ),
+            (None, Some(_)) => validator.error(
+                "cost",
+                REQUIRED,
+                String::from("cost is required with a currency"),
+            ),
+            (None, None) => {}
+        }
+        if let Some(engine_hours) = self.engine_hours {
+            validator.range("engine_hours", engine_hours, 0.0, MAX_ENGINE_HOURS);
+        }
+        if self.performed_at > Utc::now() {
+            validator.error(
+                "performed_at",
+                INVALID_VALUE,
+                String::from("performed_at must not be in the future"),
+            );
+        }
+        validator.finish()
+    }
+}
+
+// period in which a boat is out of service, it counts as busy wherever availability is checked
+#[derive(Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = maintenance_windows)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct MaintenanceWindow {
+    pub id: i32,
+    pub boat_id: i32,
+    pub service_type: String,
+    pub notes: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = maintenance_windows)]
+pub struct NewMaintenanceWindow {
+    #[serde(skip_deserializing)]
+    pub boat_id: i32,
+    pub service_type: String,
+    pub notes: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl NewMaintenanceWindow {
+    pub fn validate(&mut self) -> Result<(), Error> {
+        let mut validator = Validator::default();
+        validate_service(&mut validator, &mut self.service_type, &mut self.notes);
+        if self.ends_at <= self.starts_at {
+            validator.error(
+                "ends_at",
+                INVALID_VALUE,
+                String::from("ends_at must be after starts_at"),
+            );
+        }
+        validator.finish()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = maintenance_intervals)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct MaintenanceInterval {
+    #[serde(skip_deserializing)]
+    pub boat_id: i32,
+    pub service_type: String,
+    pub every_days: Option<i32>,
+    pub every_engine_hours: Option<f32>,
+}
+
+impl MaintenanceInterval {
+    pub fn validate(&mut self) -> Result<(), Error> {
+        let mut validator = Validator::default();
+        validate_service(&mut validator, &mut self.service_type, &mut None);
+        if let Some(every_days) = self.every_days {
+            validator.range("every_days", every_days, 1, MAX_INTERVAL_DAYS);
+        }
+        if let Some(every_engine_hours) = self.every_engine_hours {
+            validator.range(
+                "every_engine_hours",
+                every_engine_hours,
+                1.0,
+                MAX_ENGINE_HOURS,
+            );
+        }
+        if self.every_days.is_none() && self.every_engine_hours.is_none() {
+            validator.error(
+                "every_days",
+                REQUIRED,
+                String::from("every_days or every_engine_hours is required"),
+            );
+        }
+        validator.finish()
+    }
+}
+
+// a service interval together with what is known about the last time it was done
+#[derive(QueryableByName)]
+pub struct ServiceStatus {
+    #[diesel(sql_type = Integer)]
+    pub boat_id: i32,
+    #[diesel(sql_type = Text)]
+    pub boat_name: String,
+    #[diesel(sql_type = TimestamptzSqlite)]
+    pub boat_created_at: DateTime<Utc>,
+    #[diesel(sql_type = Text)]
+    pub service_type: String,
+    #[diesel(sql_type = Nullable<Integer>)]
+    pub every_days: Option<i32>,
+    #[diesel(sql_type = Nullable<Float>)]
+    pub every_engine_hours: Option<f32>,
+    #[diesel(sql_type = Nullable<TimestamptzSqlite>)]
+    pub last_performed_at: Option<DateTime<Utc>>,
+    // meter reading at the last service of this type
+    #[diesel(sql_type = Nullable<Float>)]
+    pub last_engine_hours: Option<f32>,
+    // latest meter reading of the boat from any service
+    #[diesel(sql_type = Nullable<Float>)]
+    pub engine_hours: Option<f32>,
+}
+
+#[derive(Serialize)]
+pub struct OverdueService {
+    pub boat_id: i32,
+    pub boat_name: String,
+    pub service_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_performed_at: Option<DateTime<Utc>>,
+    // when the service fell due by elapsed time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    // engine hours run since the last service of this type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_hours_since: Option<f32>,
+}
+
+fn validate_service(
+    validator: &mut Validator,
+    service_type: &mut String,
+    notes: &mut Option<String>,
+) {
+    let trimmed = service_type.trim();
+    if trimmed.len() != service_type.len() {
+        *service_type = trimmed.to_owned();
+    }
+    validator.text("service_type", service_type, MAX_SERVICE_TYPE_LENGTH);
+    // blank notes are stored as no notes
+    if notes
+        .as_deref()
+        .is_some_and(|value| value.trim().is_empty())
+    {
+        *notes = None;
+    }
+    if let Some(notes) = notes {
+        validator.text("notes", notes, MAX_NOTES_LENGTH);
+    }
+}
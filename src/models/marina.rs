@@ -0,0 +1,103 @@
+use crate::schema::marinas;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = marinas)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Marina {
+    pub id: i32,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = marinas)]
+pub struct NewMarina {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    // IANA time zone name, e.g. Europe/London
+    pub timezone: String,
+}
+
+// parsed from `lat,lon`, e.g. near=50.89,-1.39
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct GeoPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl TryFrom<String> for GeoPoint {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let coordinates = parse_coordinates(&value, 2)?;
+        let point = GeoPoint {
+            latitude: coordinates[0],
+            longitude: coordinates[1],
+        };
+        if !valid_latitude(point.latitude) || !valid_longitude(point.longitude) {
+            return Err(format!("Coordinates out of range: {}", value));
+        }
+        Ok(point)
+    }
+}
+
+// parsed from `min_lon,min_lat,max_lon,max_lat`. boxes crossing the antimeridian have
+// min_lon > max_lon
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct BoundingBox {
+    pub min_longitude: f64,
+    pub min_latitude: f64,
+    pub max_longitude: f64,
+    pub max_latitude: f64,
+}
+
+impl TryFrom<String> for BoundingBox {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let coordinates = parse_coordinates(&value, 4)?;
+        let bbox = BoundingBox {
+            min_longitude: coordinates[0],
+            min_latitude: coordinates[1],
+            max_longitude: coordinates[2],
+            max_latitude: coordinates[3],
+        };
+        if !valid_longitude(bbox.min_longitude)
+            || !valid_longitude(bbox.max_longitude)
+            || !valid_latitude(bbox.min_latitude)
+            || !valid_latitude(bbox.max_latitude)
+            || bbox.min_latitude > bbox.max_latitude
+        {
+            return Err(format!("Invalid bounding box: {}", value));
+        }
+        Ok(bbox)
+    }
+}
+
+pub fn valid_latitude(latitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude)
+}
+
+pub fn valid_longitude(longitude: f64) -> bool {
+    (-180.0..=180.0).contains(&longitude)
+}
+
+fn parse_coordinates(value: &str, count: usize) -> Result<Vec<f64>, String> {
+    let coordinates = value
+        .split(',')
+        .map(|coordinate| coordinate.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("Invalid coordinates: {}", value))?;
+    if coordinates.len() != count || coordinates.iter().any(|c| !c.is_finite()) {
+        return Err(format!("Invalid coordinates: {}", value));
+    }
+    Ok(coordinates)
+}
@@ -0,0 +1,38 @@
+use crate::schema::boat_media;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::Serialize;
+
+// photo or document attached to a boat, the file itself lives in storage
+#[derive(Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = boat_media)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Media {
+    pub id: i32,
+    pub boat_id: i32,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    #[serde(skip_serializing)]
+    pub thumbnail_key: Option<String>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub checksum: String,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = boat_media)]
+pub struct NewMedia {
+    pub boat_id: i32,
+    pub storage_key: String,
+    pub thumbnail_key: Option<String>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub checksum: String,
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,3 +1,15 @@
+pub mod availability;
+pub mod batch;
+pub mod blackout;
 pub mod boat;
+pub mod booking;
+pub mod import;
 pub mod jwt;
+pub mod maintenance;
+pub mod marina;
+pub mod media;
+pub mod pricing;
+pub mod review;
+pub mod revision;
+pub mod tag;
 pub mod user;
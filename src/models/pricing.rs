@@ -0,0 +1,74 @@
+use crate::schema::{rate_cards, seasonal_rates};
+use chrono::{DateTime, NaiveDate, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+// amounts are in minor currency units, e.g. cents
+#[derive(Deserialize, Serialize, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = rate_cards)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct RateCard {
+    #[serde(skip_deserializing)]
+    pub boat_id: i32,
+    pub currency: String,
+    pub hourly_rate: Option<i64>,
+    pub daily_rate: Option<i64>,
+    pub weekly_rate: Option<i64>,
+    pub min_hours: i32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = seasonal_rates)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct SeasonalRate {
+    pub id: i32,
+    pub boat_id: i32,
+    pub name: String,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub multiplier: f64,
+}
+
+// seasons cover whole days, from `starts_on` up to and including `ends_on`
+#[derive(Deserialize)]
+pub struct NewSeasonalRate {
+    pub name: String,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub multiplier: f64,
+}
+
+#[derive(Serialize)]
+pub struct Rates {
+    pub rate_card: RateCard,
+    pub seasons: Vec<SeasonalRate>,
+}
+
+#[derive(Deserialize)]
+pub struct QuoteRequest {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct LineItem {
+    pub description: String,
+    pub quantity: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<i64>,
+    pub amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct Quote {
+    pub boat_id: i32,
+    pub currency: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub hours: i64,
+    // rentals shorter than the boat's minimum are charged for the minimum
+    pub billed_hours: i64,
+    pub items: Vec<LineItem>,
+    pub total: i64,
+}
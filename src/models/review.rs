@@ -0,0 +1,45 @@
+use crate::{errors::Error, schema::reviews, validation::Validator};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_RATING: i32 = 1;
+pub const MAX_RATING: i32 = 5;
+pub const MAX_REVIEW_LENGTH: usize = 5000;
+
+#[derive(Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = reviews)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Review {
+    pub id: i32,
+    pub boat_id: i32,
+    pub user_email: String,
+    pub rating: i32,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// body of a new or edited review
+#[derive(Deserialize)]
+pub struct NewReview {
+    pub rating: i32,
+    pub text: String,
+}
+
+impl NewReview {
+    pub fn validate(&mut self) -> Result<(), Error> {
+        let mut validator = Validator::default();
+        validator.range("rating", self.rating, MIN_RATING, MAX_RATING);
+        self.text = self.text.trim().to_owned();
+        validator.text("text", &self.text, MAX_REVIEW_LENGTH);
+        validator.finish()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReviewQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
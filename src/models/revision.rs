@@ -0,0 +1,69 @@
+use crate::schema::boat_revisions;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::Serialize;
+use serde_json::Value;
+
+// values of a boat before a change, `snapshot` is the boat serialized as JSON
+#[derive(Clone, Queryable, Selectable)]
+#[diesel(table_name = boat_revisions)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Revision {
+    pub id: i32,
+    pub boat_id: i32,
+    pub action: String,
+    pub actor: String,
+    pub snapshot: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = boat_revisions)]
+pub struct NewRevision {
+    pub boat_id: i32,
+    pub action: String,
+    pub actor: String,
+    pub snapshot: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy)]
+pub enum RevisionAction {
+    Update,
+    Delete,
+    Revert,
+    Restore,
+    Purge,
+}
+
+impl RevisionAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RevisionAction::Update => "update",
+            RevisionAction::Delete => "delete",
+            RevisionAction::Revert => "revert",
+            RevisionAction::Restore => "restore",
+            RevisionAction::Purge => "purge",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Serialize)]
+pub struct RevisionEntry {
+    pub id: i32,
+    pub boat_id: i32,
+    pub action: String,
+    // left out for anyone but the owner of the boat and admins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub changes: Vec<FieldChange>,
+}
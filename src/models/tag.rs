@@ -0,0 +1,103 @@
+use crate::{
+    errors::Error,
+    schema::tags,
+    validation::{Validator, INVALID_VALUE, TOO_LONG},
+};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_TAG_NAME_LENGTH: usize = 40;
+pub const MAX_DESCRIPTION_LENGTH: usize = 200;
+
+#[derive(Serialize, Clone, Queryable, Selectable)]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub category: Option<String>,
+    pub description: Option<String>,
+}
+
+// written as a whole on updates, so a missing category or description clears it
+#[derive(Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = tags)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewTag {
+    pub name: String,
+    pub category: Option<String>,
+    pub description: Option<String>,
+}
+
+impl NewTag {
+    pub fn validate(&mut self) -> Result<(), Error> {
+        let mut validator = Validator::default();
+        self.name = self.name.trim().to_owned();
+        validate_slug(&mut validator, "name", &self.name);
+        // blank values are stored as no value
+        for value in [&mut self.category, &mut self.description] {
+            if value
+                .as_deref()
+                .is_some_and(|value| value.trim().is_empty())
+            {
+                *value = None;
+            }
+        }
+        if let Some(category) = &mut self.category {
+            *category = category.trim().to_owned();
+            validate_slug(&mut validator, "category", category);
+        }
+        if let Some(description) = &self.description {
+            if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+                validator.error(
+                    "description",
+                    TOO_LONG,
+                    format!(
+                        "description must be at most {} characters",
+                        MAX_DESCRIPTION_LENGTH
+                    ),
+                );
+            }
+        }
+        validator.finish()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TagQuery {
+    pub category: Option<String>,
+}
+
+// how the tags of a `tag=` listing filter are combined
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    // boats with at least one of the tags
+    #[default]
+    Any,
+    // boats with every one of the tags
+    All,
+}
+
+// tag names are lowercase words joined by hyphens, e.g. pet-friendly
+fn validate_slug(validator: &mut Validator, field: &'static str, value: &str) {
+    validator.text(field, value, MAX_TAG_NAME_LENGTH);
+    if !value.is_empty()
+        && !value.split('-').all(|word| {
+            !word.is_empty()
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        })
+    {
+        validator.error(
+            field,
+            INVALID_VALUE,
+            format!(
+                "{} must be lowercase letters and digits separated by hyphens",
+                field
+            ),
+        );
+    }
+}
@@ -10,6 +10,7 @@ pub struct User {
     pub email: String,
     pub api_key: String,
     pub credit: i32,
+    pub is_admin: bool,
 }
 
 #[derive(Deserialize, Insertable)]
@@ -0,0 +1,84 @@
+use crate::errors::Error;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+// cursors are opaque to clients: url-safe base64 of the JSON-encoded position
+pub fn encode_cursor<T: Serialize>(cursor: &T) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).expect("Failed to serialize cursor"))
+}
+
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Result<T, Error> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| Error::InvalidParameter)?;
+    serde_json::from_slice(&bytes).map_err(|_| Error::InvalidParameter)
+}
+
+// query params that are not carried over into links. Paging params are replaced by the new
+// cursor, and the API key would otherwise end up in logs and caches along with the header
+const DROPPED_PARAMS: [&str; 3] = ["cursor", "offset", "api_key"];
+
+// builds an RFC 8288 Link header value, e.g. `</boats?cursor=abc>; rel="next"`
+pub fn link_header(path: &str, raw_query: &str, links: &[(&str, String)]) -> String {
+    let params: Vec<&str> = raw_query
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default();
+            !param.is_empty() && !DROPPED_PARAMS.contains(&name)
+        })
+        .collect();
+    links
+        .iter()
+        .map(|(rel, cursor)| {
+            let mut query = params.clone();
+            let cursor = format!("cursor={}", cursor);
+            query.push(&cursor);
+            format!("<{}?{}>; rel=\"{}\"", path, query.join("&"), rel)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_cursors() {
+        let cursor = (String::from("Sea Breeze"), 42);
+        let encoded = encode_cursor(&cursor);
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode_cursor::<(String, i32)>(&encoded).unwrap(), cursor);
+        assert!(decode_cursor::<(String, i32)>("not a cursor").is_err());
+    }
+
+    #[test]
+    fn builds_links_with_the_new_cursor() {
+        let header = link_header(
+            "/boats",
+            "make=Beneteau&cursor=old&limit=10",
+            &[("next", String::from("abc")), ("prev", String::from("xyz"))],
+        );
+        assert_eq!(
+            header,
+            "</boats?make=Beneteau&limit=10&cursor=abc>; rel=\"next\", \
+             </boats?make=Beneteau&limit=10&cursor=xyz>; rel=\"prev\""
+        );
+    }
+
+    #[test]
+    fn leaves_the_api_key_and_offset_out_of_links() {
+        let header = link_header(
+            "/boats",
+            "api_key=secret&offset=20&sort=name&cursor",
+            &[("next", String::from("abc"))],
+        );
+        assert_eq!(header, "</boats?sort=name&cursor=abc>; rel=\"next\"");
+    }
+}
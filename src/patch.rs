@@ -0,0 +1,306 @@
+use crate::errors::Error;
+use serde_json::{Map, Value};
+
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+// RFC 7396: objects are merged recursively, null removes a member, anything else replaces
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!();
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+// RFC 6902: operations are applied in order and the document is left untouched if any fails.
+// Malformed operations are invalid parameters, operations that don't fit the document conflict
+// with it.
+pub fn json_patch(target: &mut Value, patch: &Value) -> Result<(), Error> {
+    let operations = patch.as_array().ok_or(Error::InvalidParameter)?;
+    let mut document = target.clone();
+    for operation in operations {
+        apply(&mut document, operation)?;
+    }
+    *target = document;
+    Ok(())
+}
+
+fn apply(document: &mut Value, operation: &Value) -> Result<(), Error> {
+    let member = |name: &str| operation.get(name).ok_or(Error::InvalidParameter);
+    let pointer = |name: &str| {
+        member(name)?
+            .as_str()
+            .ok_or(Error::InvalidParameter)
+            .and_then(parse_pointer)
+    };
+    let path = pointer("path")?;
+    match member("op")?.as_str().ok_or(Error::InvalidParameter)? {
+        "add" => add(document, &path, member("value")?.clone()),
+        "remove" => remove(document, &path).map(|_| ()),
+        "replace" => {
+            remove(document, &path)?;
+            add(document, &path, member("value")?.clone())
+        }
+        "move" => {
+            let from = pointer("from")?;
+            // a value can't be moved into one of its own children
+            if path.len() > from.len() && path.starts_with(&from) {
+                return Err(Error::InvalidParameter);
+            }
+            let value = remove(document, &from)?;
+            add(document, &path, value)
+        }
+        "copy" => {
+            let value = get(document, &pointer("from")?)?.clone();
+            add(document, &path, value)
+        }
+        "test" => {
+            if get(document, &path)? == member("value")? {
+                Ok(())
+            } else {
+                Err(Error::Conflict)
+            }
+        }
+        _ => Err(Error::InvalidParameter),
+    }
+}
+
+// RFC 6901 JSON pointer, e.g. `/a~1b/0` addresses `["a/b"][0]`
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let tokens = pointer.strip_prefix('/').ok_or(Error::InvalidParameter)?;
+    Ok(tokens
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn get<'a>(document: &'a Value, path: &[String]) -> Result<&'a Value, Error> {
+    path.iter().try_fold(document, |value, token| match value {
+        Value::Object(map) => map.get(token).ok_or(Error::Conflict),
+        Value::Array(items) => items.get(array_index(token)?).ok_or(Error::Conflict),
+        _ => Err(Error::Conflict),
+    })
+}
+
+fn add(document: &mut Value, path: &[String], value: Value) -> Result<(), Error> {
+    let Some((last, parent)) = path.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+    match parent_mut(document, parent)? {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            let index = if last == "-" {
+                items.len()
+            } else {
+                array_index(last)?
+            };
+            if index > items.len() {
+                return Err(Error::Conflict);
+            }
+            items.insert(index, value);
+            Ok(())
+        }
+        _ => Err(Error::Conflict),
+    }
+}
+
+fn remove(document: &mut Value, path: &[String]) -> Result<Value, Error> {
+    let Some((last, parent)) = path.split_last() else {
+        return Ok(std::mem::take(document));
+    };
+    match parent_mut(document, parent)? {
+        Value::Object(map) => map.remove(last).ok_or(Error::Conflict),
+        Value::Array(items) => {
+            let index = array_index(last)?;
+            if index >= items.len() {
+                return Err(Error::Conflict);
+            }
+            Ok(items.remove(index))
+        }
+        _ => Err(Error::Conflict),
+    }
+}
+
+fn parent_mut<'a>(document: &'a mut Value, path: &[String]) -> Result<&'a mut Value, Error> {
+    path.iter().try_fold(document, |value, token| match value {
+        Value::Object(map) => map.get_mut(token).ok_or(Error::Conflict),
+        Value::Array(items) => {
+            let index = array_index(token)?;
+            items.get_mut(index).ok_or(Error::Conflict)
+        }
+        _ => Err(Error::Conflict),
+    })
+}
+
+// array indices are plain decimal numbers without leading zeros
+fn array_index(token: &str) -> Result<usize, Error> {
+    if token.is_empty()
+        || !token.bytes().all(|byte| byte.is_ascii_digit())
+        || token.len() > 1 && token.starts_with('0')
+    {
+        return Err(Error::Conflict);
+    }
+    token.parse().map_err(|_| Error::Conflict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patched(mut document: Value, patch: Value) -> Result<Value, Error> {
+        json_patch(&mut document, &patch)?;
+        Ok(document)
+    }
+
+    #[test]
+    fn merges_patches() {
+        // examples from RFC 7396 appendix A
+        for (target, patch, result) in [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!([1, 2]),
+                json!({"a": "b", "c": null}),
+                json!({"a": "b"}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ] {
+            let mut document = target;
+            merge_patch(&mut document, &patch);
+            assert_eq!(document, result);
+        }
+    }
+
+    #[test]
+    fn applies_operations_in_order() {
+        let document = json!({"name": "Sea Breeze", "tags": ["a", "c"]});
+        let result = patched(
+            document,
+            json!([
+                {"op": "test", "path": "/name", "value": "Sea Breeze"},
+                {"op": "replace", "path": "/name", "value": "Wave"},
+                {"op": "add", "path": "/tags/1", "value": "b"},
+                {"op": "add", "path": "/tags/-", "value": "d"},
+                {"op": "copy", "from": "/name", "path": "/model"},
+                {"op": "move", "from": "/model", "path": "/make"},
+                {"op": "remove", "path": "/tags/0"}
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!({"name": "Wave", "tags": ["b", "c", "d"], "make": "Wave"})
+        );
+    }
+
+    #[test]
+    fn leaves_the_document_untouched_when_an_operation_fails() {
+        let mut document = json!({"name": "Sea Breeze"});
+        let patch = json!([
+            {"op": "replace", "path": "/name", "value": "Wave"},
+            {"op": "test", "path": "/name", "value": "Sea Breeze"}
+        ]);
+        assert!(matches!(
+            json_patch(&mut document, &patch),
+            Err(Error::Conflict)
+        ));
+        assert_eq!(document, json!({"name": "Sea Breeze"}));
+    }
+
+    #[test]
+    fn rejects_malformed_operations() {
+        for patch in [
+            json!({"op": "add", "path": "/a", "value": 1}),
+            json!([{"op": "add", "value": 1}]),
+            json!([{"op": "add", "path": "/a"}]),
+            json!([{"op": "frobnicate", "path": "/a"}]),
+            json!([{"op": "add", "path": "a", "value": 1}]),
+            json!([{"op": "move", "from": "/a", "path": "/a/b"}]),
+        ] {
+            assert!(matches!(
+                patched(json!({"a": {}}), patch),
+                Err(Error::InvalidParameter)
+            ));
+        }
+    }
+
+    #[test]
+    fn conflicts_with_paths_missing_from_the_document() {
+        for patch in [
+            json!([{"op": "remove", "path": "/missing"}]),
+            json!([{"op": "replace", "path": "/missing", "value": 1}]),
+            json!([{"op": "add", "path": "/missing/a", "value": 1}]),
+            json!([{"op": "add", "path": "/list/3", "value": 1}]),
+            json!([{"op": "remove", "path": "/list/01"}]),
+            json!([{"op": "test", "path": "/list/0", "value": 2}]),
+        ] {
+            assert!(matches!(
+                patched(json!({"list": [1]}), patch),
+                Err(Error::Conflict)
+            ));
+        }
+    }
+
+    #[test]
+    fn unescapes_pointers() {
+        assert_eq!(parse_pointer("").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            parse_pointer("/a~1b/~0c/0").unwrap(),
+            vec!["a/b", "~c", "0"]
+        );
+        let result = patched(
+            json!({"a/b": 1}),
+            json!([{"op": "move", "from": "/a~1b", "path": "/~0"}]),
+        )
+        .unwrap();
+        assert_eq!(result, json!({"~": 1}));
+    }
+}
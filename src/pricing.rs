@@ -0,0 +1,431 @@
+use crate::{
+    errors::Error,
+    models::pricing::{LineItem, Quote, RateCard, SeasonalRate},
+    validation::{FieldError, Validator, INVALID_VALUE, OUT_OF_RANGE, REQUIRED},
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+
+const HOURS_PER_DAY: i64 = 24;
+const HOURS_PER_WEEK: i64 = 7 * HOURS_PER_DAY;
+// highest rate in minor currency units, i.e. 10 million in major units
+pub const MAX_RATE: i64 = 1_000_000_000;
+
+pub fn validate_rate_card(card: &RateCard) -> Result<(), Error> {
+    let mut validator = Validator::default();
+    let rates = [
+        ("hourly_rate", card.hourly_rate),
+        ("daily_rate", card.daily_rate),
+        ("weekly_rate", card.weekly_rate),
+    ];
+    if rates.iter().all(|(_, rate)| rate.is_none()) {
+        validator.error(
+            "hourly_rate",
+            REQUIRED,
+            String::from("at least one of hourly_rate, daily_rate and weekly_rate is required"),
+        );
+    }
+    for (field, rate) in rates {
+        if let Some(rate) = rate {
+            validator.range(field, rate, 1, MAX_RATE);
+        }
+    }
+    if card.min_hours < 1 {
+        validator.error(
+            "min_hours",
+            OUT_OF_RANGE,
+            String::from("min_hours must be at least 1"),
+        );
+    }
+    if card.currency.len() != 3 || !card.currency.chars().all(|c| c.is_ascii_uppercase()) {
+        validator.error(
+            "currency",
+            INVALID_VALUE,
+            String::from("currency must be a three letter ISO 4217 code"),
+        );
+    }
+    validator.finish()
+}
+
+// prices a rental as whole weeks, days and hours at the base rates, then adjusts the share of
+// the rental that falls into each season by that season's multiplier
+pub fn quote(
+    card: &RateCard,
+    seasons: &[SeasonalRate],
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<Quote, Error> {
+    let (hours, billed_hours) = billed_hours(card, starts_at, ends_at)?;
+    let mut items = base_items(card, billed_hours)?;
+    let base_total = sum(&items)?;
+
+    let billed_until = billed_until(card, starts_at, ends_at)?;
+    let billed_seconds = (billed_until - starts_at).num_seconds() as f64;
+    for season in seasons {
+        let season_start = season.starts_on.and_time(NaiveTime::MIN).and_utc();
+        let season_end = season
+            .ends_on
+            .succ_opt()
+            .unwrap_or(NaiveDate::MAX)
+            .and_time(NaiveTime::MIN)
+            .and_utc();
+        let overlap = billed_until.min(season_end) - starts_at.max(season_start);
+        if overlap <= Duration::zero() {
+            continue;
+        }
+        let share = overlap.num_seconds() as f64 / billed_seconds;
+        let amount = (base_total as f64 * share * (season.multiplier - 1.0)).round();
+        // casting would saturate amounts that do not fit instead of failing
+        if !amount.is_finite() || amount.abs() >= i64::MAX as f64 {
+            return Err(too_large());
+        }
+        items.push(LineItem {
+            description: format!("{} (x{})", season.name, season.multiplier),
+            quantity: (overlap.num_seconds() + 3599) / 3600,
+            unit_price: None,
+            amount: amount as i64,
+        });
+    }
+    let total = sum(&items)?;
+
+    Ok(Quote {
+        boat_id: card.boat_id,
+        currency: card.currency.clone(),
+        starts_at,
+        ends_at,
+        hours,
+        billed_hours,
+        total,
+        items,
+    })
+}
+
+// hours of a rental, and the hours it is charged for
+fn billed_hours(
+    card: &RateCard,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<(i64, i64), Error> {
+    if starts_at >= ends_at {
+        return Err(Error::InvalidParameter);
+    }
+    // part hours are charged as whole hours
+    let seconds = (ends_at - starts_at).num_seconds();
+    let hours = (seconds + 3599) / 3600;
+    Ok((hours, hours.max(card.min_hours.into())))
+}
+
+// end of the time a rental is charged for, which runs past `ends_at` for rentals shorter than
+// the minimum. Seasons apply up to here
+pub fn billed_until(
+    card: &RateCard,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<DateTime<Utc>, Error> {
+    let (_, billed_hours) = billed_hours(card, starts_at, ends_at)?;
+    Duration::try_hours(billed_hours)
+        .and_then(|billed| starts_at.checked_add_signed(billed))
+        .ok_or_else(too_large)
+}
+
+fn sum(items: &[LineItem]) -> Result<i64, Error> {
+    items
+        .iter()
+        .try_fold(0i64, |total, item| total.checked_add(item.amount))
+        .ok_or_else(too_large)
+}
+
+// amounts are whole numbers of minor units, so very long rentals at high rates cannot be priced
+fn too_large() -> Error {
+    Error::Validation(vec![FieldError {
+        field: "ends_at".into(),
+        code: OUT_OF_RANGE,
+        message: String::from("the rental is too long to be priced"),
+    }])
+}
+
+fn cost(quantity: i64, rate: i64) -> Result<i64, Error> {
+    quantity.checked_mul(rate).ok_or_else(too_large)
+}
+
+fn base_items(card: &RateCard, hours: i64) -> Result<Vec<LineItem>, Error> {
+    let mut weeks = hours / HOURS_PER_WEEK;
+    let mut days = hours % HOURS_PER_WEEK / HOURS_PER_DAY;
+    let mut remaining_hours = hours % HOURS_PER_DAY;
+
+    // units without a rate are charged in the next smaller unit, or rounded up to the next
+    // larger one if there is none
+    if card.weekly_rate.is_none() {
+        days += weeks * 7;
+        weeks = 0;
+    }
+    if card.hourly_rate.is_none() && remaining_hours > 0 {
+        days += 1;
+        remaining_hours = 0;
+    }
+    if card.daily_rate.is_none() && days > 0 {
+        if card.hourly_rate.is_some() {
+            remaining_hours += days * HOURS_PER_DAY;
+        } else {
+            weeks += 1;
+        }
+        days = 0;
+    }
+
+    // the remainder never costs more than the next larger unit would
+    if let (Some(hourly_rate), Some(daily_rate)) = (card.hourly_rate, card.daily_rate) {
+        if cost(remaining_hours, hourly_rate)? > daily_rate {
+            days += 1;
+            remaining_hours = 0;
+        }
+    }
+    if let Some(weekly_rate) = card.weekly_rate {
+        let remainder = cost(days, card.daily_rate.unwrap_or_default())?
+            .checked_add(cost(remaining_hours, card.hourly_rate.unwrap_or_default())?)
+            .ok_or_else(too_large)?;
+        if remainder > weekly_rate {
+            weeks += 1;
+            days = 0;
+            remaining_hours = 0;
+        }
+    }
+
+    [
+        ("week", weeks, card.weekly_rate),
+        ("day", days, card.daily_rate),
+        ("hour", remaining_hours, card.hourly_rate),
+    ]
+    .into_iter()
+    .filter_map(|(unit, quantity, rate)| match (quantity, rate) {
+        (1.., Some(rate)) => Some(cost(quantity, rate).map(|amount| LineItem {
+            description: format!("{} x {}", quantity, unit),
+            quantity,
+            unit_price: Some(rate),
+            amount,
+        })),
+        _ => None,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(
+        hourly_rate: Option<i64>,
+        daily_rate: Option<i64>,
+        weekly_rate: Option<i64>,
+    ) -> RateCard {
+        RateCard {
+            boat_id: 1,
+            currency: String::from("EUR"),
+            hourly_rate,
+            daily_rate,
+            weekly_rate,
+            min_hours: 1,
+        }
+    }
+
+    fn season(starts_on: &str, ends_on: &str, multiplier: f64) -> SeasonalRate {
+        SeasonalRate {
+            id: 1,
+            boat_id: 1,
+            name: String::from("Summer"),
+            starts_on: starts_on.parse().unwrap(),
+            ends_on: ends_on.parse().unwrap(),
+            multiplier,
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn lines(quote: &Quote) -> Vec<(&str, i64)> {
+        quote
+            .items
+            .iter()
+            .map(|item| (item.description.as_str(), item.amount))
+            .collect()
+    }
+
+    #[test]
+    fn splits_rentals_into_weeks_days_and_hours() {
+        let card = card(Some(1_000), Some(10_000), Some(50_000));
+        let quote = quote(
+            &card,
+            &[],
+            at("2024-06-01T10:00:00Z"),
+            at("2024-06-10T12:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(quote.hours, 9 * 24 + 2);
+        assert_eq!(
+            lines(&quote),
+            vec![
+                ("1 x week", 50_000),
+                ("2 x day", 20_000),
+                ("2 x hour", 2_000)
+            ]
+        );
+        assert_eq!(quote.total, 72_000);
+    }
+
+    #[test]
+    fn charges_part_hours_as_whole_hours() {
+        let card = card(Some(1_000), None, None);
+        let quote = quote(
+            &card,
+            &[],
+            at("2024-06-01T10:00:00Z"),
+            at("2024-06-01T11:00:01Z"),
+        )
+        .unwrap();
+        assert_eq!(quote.hours, 2);
+        assert_eq!(quote.total, 2_000);
+    }
+
+    #[test]
+    fn charges_at_least_the_minimum_hours() {
+        let card = RateCard {
+            min_hours: 4,
+            ..card(Some(1_000), None, None)
+        };
+        let quote = quote(
+            &card,
+            &[],
+            at("2024-06-01T10:00:00Z"),
+            at("2024-06-01T11:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(quote.hours, 1);
+        assert_eq!(quote.billed_hours, 4);
+        assert_eq!(quote.total, 4_000);
+    }
+
+    #[test]
+    fn caps_remainders_at_the_next_larger_unit() {
+        // 9 hours cost more than a day, and then 7 days more than a week
+        let card = card(Some(1_000), Some(8_000), Some(40_000));
+        let quote = quote(
+            &card,
+            &[],
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-07T09:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(lines(&quote), vec![("1 x week", 40_000)]);
+    }
+
+    #[test]
+    fn falls_back_to_the_units_that_have_a_rate() {
+        // no weekly rate: 8 days are charged as days, the extra hour rounds up to a day
+        let days = card(None, Some(10_000), None);
+        let by_day = quote(
+            &days,
+            &[],
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-09T01:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(lines(&by_day), vec![("9 x day", 90_000)]);
+
+        // no daily rate: days are charged as hours
+        let hours = card(Some(1_000), None, Some(500_000));
+        let by_hour = quote(
+            &hours,
+            &[],
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-02T02:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(lines(&by_hour), vec![("26 x hour", 26_000)]);
+
+        // only a weekly rate: a single day costs a week
+        let weeks = card(None, None, Some(50_000));
+        let by_week = quote(
+            &weeks,
+            &[],
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-02T00:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(lines(&by_week), vec![("1 x week", 50_000)]);
+    }
+
+    #[test]
+    fn adjusts_the_share_of_the_rental_in_each_season() {
+        let card = card(None, Some(10_000), None);
+        // two of the four days fall into a season charging 50% more
+        let seasons = [season("2024-06-03", "2024-06-30", 1.5)];
+        let quote = quote(
+            &card,
+            &seasons,
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-05T00:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(
+            lines(&quote),
+            vec![("4 x day", 40_000), ("Summer (x1.5)", 10_000)]
+        );
+        assert_eq!(quote.items[1].quantity, 48);
+        assert_eq!(quote.total, 50_000);
+    }
+
+    #[test]
+    fn rejects_empty_periods() {
+        let card = card(Some(1_000), None, None);
+        let time = at("2024-06-01T10:00:00Z");
+        assert!(matches!(
+            quote(&card, &[], time, time),
+            Err(Error::InvalidParameter)
+        ));
+    }
+
+    #[test]
+    fn rejects_totals_that_do_not_fit() {
+        // rate cards stored before rates were limited
+        let hourly = card(Some(i64::MAX), None, None);
+        let result = quote(
+            &hourly,
+            &[],
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-01T02:00:00Z"),
+        );
+        assert!(matches!(result, Err(Error::Validation(_))));
+
+        let daily = card(None, Some(MAX_RATE), None);
+        let seasons = [season("2024-06-01", "2024-06-30", 1e300)];
+        let result = quote(
+            &daily,
+            &seasons,
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-02T00:00:00Z"),
+        );
+        assert!(matches!(result, Err(Error::Validation(_))));
+    }
+
+    #[test]
+    fn validates_rate_cards() {
+        assert!(validate_rate_card(&card(Some(MAX_RATE), None, None)).is_ok());
+        for invalid in [
+            card(None, None, None),
+            card(Some(0), None, None),
+            card(Some(MAX_RATE + 1), None, None),
+            RateCard {
+                min_hours: 0,
+                ..card(Some(1_000), None, None)
+            },
+            RateCard {
+                currency: String::from("eur"),
+                ..card(Some(1_000), None, None)
+            },
+        ] {
+            assert!(matches!(
+                validate_rate_card(&invalid),
+                Err(Error::Validation(_))
+            ));
+        }
+    }
+}
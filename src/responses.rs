@@ -1,3 +1,4 @@
+use crate::validation::FieldError;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -9,4 +10,12 @@ pub struct TokenResponse {
 pub struct ErrorResponse {
     pub message: String,
     pub status: String,
+    // offending fields of a failed validation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Serialize)]
+pub struct PurgeResponse {
+    pub purged: Vec<i32>,
 }
@@ -0,0 +1,33 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers,
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_query, with_units, with_user},
+};
+use warp::Filter;
+
+const MAX_BATCH_SIZE: u64 = 1024 * 1024;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    run_batch(pool, rate_limiter)
+}
+
+// the API key is processed once, so a batch is charged like a single request
+fn run_batch(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / "batch")
+        .and(warp::post())
+        .and(with_query())
+        .and(with_units())
+        .and(warp::body::content_length_limit(MAX_BATCH_SIZE))
+        .and(warp::body::json())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::batch::run_batch)
+}
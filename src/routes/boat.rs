@@ -2,19 +2,31 @@ use crate::{
     db::SharedConnectionPool,
     handlers,
     rate_limiting::KeyedRateLimiter,
-    routes::filters::{process_api_key, with_db},
+    routes::filters::{
+        process_api_key, with_conditions, with_db, with_query, with_raw_query, with_storage,
+        with_units, with_user,
+    },
+    storage::SharedStorage,
 };
 use warp::Filter;
 
+const MAX_PATCH_SIZE: u64 = 64 * 1024;
+
 pub fn routes(
     pool: SharedConnectionPool,
     rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     get_all_boats(pool.clone(), rate_limiter.clone())
+        .or(search_boats(pool.clone(), rate_limiter.clone()))
         .or(create_boat(pool.clone(), rate_limiter.clone()))
         .or(get_boat(pool.clone(), rate_limiter.clone()))
         .or(update_boat(pool.clone(), rate_limiter.clone()))
-        .or(delete_boat(pool, rate_limiter))
+        .or(patch_boat(pool.clone(), rate_limiter.clone()))
+        .or(delete_boat(pool.clone(), rate_limiter.clone()))
+        .or(get_trash(pool.clone(), rate_limiter.clone()))
+        .or(restore_boat(pool.clone(), rate_limiter.clone()))
+        .or(purge_trash(pool, rate_limiter, storage))
 }
 
 fn get_boat(
@@ -24,6 +36,8 @@ fn get_boat(
     // note: use path! macro instead of path() function
     warp::path!("boats" / i32)
         .and(warp::get())
+        .and(with_conditions())
+        .and(with_units())
         .and(process_api_key(pool.clone(), rate_limiter))
         .and(with_db(pool))
         .and_then(handlers::boat::get_boat)
@@ -35,19 +49,39 @@ fn get_all_boats(
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::path!("boats")
         .and(warp::get())
+        .and(with_query())
+        .and(with_raw_query())
+        .and(warp::header::optional::<String>("accept"))
+        .and(with_conditions())
+        .and(with_units())
         .and(process_api_key(pool.clone(), rate_limiter))
         .and(with_db(pool))
         .and_then(handlers::boat::get_all_boats)
 }
 
+fn search_boats(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / "search")
+        .and(warp::get())
+        .and(with_query())
+        .and(with_units())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::boat::search_boats)
+}
+
 fn create_boat(
     pool: SharedConnectionPool,
     rate_limiter: KeyedRateLimiter,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::path!("boats")
         .and(warp::post())
+        .and(with_units())
         .and(warp::body::json())
         .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
         .and(with_db(pool))
         .and_then(handlers::boat::create_boat)
 }
@@ -58,19 +92,81 @@ fn update_boat(
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::path!("boats" / i32)
         .and(warp::put())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_units())
         .and(warp::body::json())
         .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
         .and(with_db(pool))
         .and_then(handlers::boat::update_boat)
 }
 
+fn patch_boat(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32)
+        .and(warp::patch())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_units())
+        .and(warp::body::content_length_limit(MAX_PATCH_SIZE))
+        .and(warp::body::bytes())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::boat::patch_boat)
+}
+
 fn delete_boat(
     pool: SharedConnectionPool,
     rate_limiter: KeyedRateLimiter,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     warp::path!("boats" / i32)
         .and(warp::delete())
+        .and(warp::header::optional::<String>("if-match"))
         .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
         .and(with_db(pool))
         .and_then(handlers::boat::delete_boat)
 }
+
+fn get_trash(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / "trash")
+        .and(warp::get())
+        .and(with_units())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::boat::get_trash)
+}
+
+fn restore_boat(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "restore")
+        .and(warp::post())
+        .and(with_units())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::boat::restore_boat)
+}
+
+fn purge_trash(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / "trash" / "purge")
+        .and(warp::post())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_storage(storage))
+        .and(with_db(pool))
+        .and_then(handlers::boat::purge_trash)
+}
@@ -0,0 +1,67 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers,
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_query, with_user},
+};
+use warp::Filter;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    get_bookings(pool.clone(), rate_limiter.clone())
+        .or(get_availability(pool.clone(), rate_limiter.clone()))
+        .or(create_booking(pool.clone(), rate_limiter.clone()))
+        .or(delete_booking(pool, rate_limiter))
+}
+
+fn get_bookings(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "bookings")
+        .and(warp::get())
+        .and(with_query())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::booking::get_bookings)
+}
+
+fn create_booking(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "bookings")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::booking::create_booking)
+}
+
+fn delete_booking(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "bookings" / i32)
+        .and(warp::delete())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::booking::delete_booking)
+}
+
+fn get_availability(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "availability")
+        .and(warp::get())
+        .and(with_query())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::availability::get_availability)
+}
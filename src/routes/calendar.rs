@@ -0,0 +1,41 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers,
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_user},
+};
+use warp::Filter;
+
+const MAX_CALENDAR_SIZE: u64 = 1024 * 1024;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    export_calendar(pool.clone(), rate_limiter.clone()).or(import_calendar(pool, rate_limiter))
+}
+
+fn export_calendar(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "calendar.ics")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::calendar::export_calendar)
+}
+
+fn import_calendar(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "calendar.ics")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(MAX_CALENDAR_SIZE))
+        .and(warp::body::bytes())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::calendar::import_calendar)
+}
@@ -1,9 +1,19 @@
 use std::collections::HashMap;
 
 use crate::{
-    credit::deduct_credit, db::SharedConnectionPool, errors::Error, rate_limiting::KeyedRateLimiter,
+    conditional::Conditions,
+    credit::deduct_credit,
+    db::SharedConnectionPool,
+    errors::Error,
+    models::user::User,
+    rate_limiting::KeyedRateLimiter,
+    schema::users,
+    storage::SharedStorage,
+    units::{Units, ACCEPT_UNITS_HEADER},
 };
-use warp::{reject, Filter};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use serde::de::DeserializeOwned;
+use warp::{reject, Filter, Rejection};
 
 pub fn with_db(
     pool: SharedConnectionPool,
@@ -12,6 +22,50 @@ pub fn with_db(
     warp::any().map(move || pool.clone())
 }
 
+pub fn with_storage(
+    storage: SharedStorage,
+) -> impl Filter<Extract = (SharedStorage,), Error = std::convert::Infallible> + Clone {
+    // adds the file storage backend to the request for use in handlers
+    warp::any().map(move || storage.clone())
+}
+
+pub fn with_query<T: DeserializeOwned + Send + 'static>(
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone {
+    // deserializes typed query params, rejecting malformed values as invalid parameters
+    warp::query::<T>()
+        .or_else(|_: Rejection| async { Err::<(T,), _>(reject::custom(Error::InvalidParameter)) })
+}
+
+pub fn with_raw_query() -> impl Filter<Extract = (String,), Error = std::convert::Infallible> + Clone
+{
+    // raw query string, empty if the request has none
+    warp::query::raw().or(warp::any().map(String::new)).unify()
+}
+
+pub fn with_conditions() -> impl Filter<Extract = (Conditions,), Error = warp::Rejection> + Clone {
+    // cache validators of a conditional GET
+    warp::header::optional::<String>("if-none-match")
+        .and(warp::header::optional::<String>("if-modified-since"))
+        .map(|if_none_match, if_modified_since| Conditions {
+            if_none_match,
+            if_modified_since,
+        })
+}
+
+pub fn with_units() -> impl Filter<Extract = (Units,), Error = warp::Rejection> + Clone {
+    // units of the dimensions sent and returned, the `units` query param wins over the header
+    warp::query::<HashMap<String, String>>()
+        .and(warp::header::optional::<String>(ACCEPT_UNITS_HEADER))
+        .and_then(
+            |params: HashMap<String, String>, header: Option<String>| async move {
+                match params.get("units").or(header.as_ref()) {
+                    Some(units) => units.parse::<Units>().map_err(reject::custom),
+                    None => Ok(Units::default()),
+                }
+            },
+        )
+}
+
 pub fn process_api_key(
     pool: SharedConnectionPool,
     rate_limiter: KeyedRateLimiter,
@@ -37,9 +91,9 @@ pub fn process_api_key(
                             .map_err(|_| reject::custom(Error::ConnectionFailed))?;
 
                         // deduct credit if rate limit not exceeded
-                        deduct_credit(&api_key, &mut conn)
+                        deduct_credit(api_key, &mut conn)
                             .await
-                            .map_err(|e| reject::custom(e))?;
+                            .map_err(reject::custom)?;
 
                         Ok(())
                     }
@@ -49,3 +103,26 @@ pub fn process_api_key(
         })
         .untuple_one() // filter doesn't extract anything so that api_key param is not expected from handlers
 }
+
+pub fn with_user(
+    pool: SharedConnectionPool,
+) -> impl Filter<Extract = (User,), Error = warp::Rejection> + Clone {
+    // resolves the user owning the API key in the query params
+    warp::query::<HashMap<String, String>>().and_then(move |params: HashMap<String, String>| {
+        let pool = pool.clone();
+        async move {
+            let api_key = params
+                .get("api_key")
+                .ok_or(reject::custom(Error::MissingAPIKey))?;
+            let mut conn = pool
+                .lock()
+                .await
+                .acquire()
+                .map_err(|_| reject::custom(Error::ConnectionFailed))?;
+            users::table
+                .filter(users::api_key.eq(api_key))
+                .first::<User>(&mut conn)
+                .map_err(|_| reject::custom(Error::InvalidCredentials))
+        }
+    })
+}
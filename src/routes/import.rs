@@ -0,0 +1,30 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers,
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_query, with_units, with_user},
+};
+use warp::Filter;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    import_boats(pool, rate_limiter)
+}
+
+fn import_boats(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / "import")
+        .and(warp::post())
+        .and(with_query())
+        .and(with_units())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::body::stream())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::import::import_boats)
+}
@@ -0,0 +1,132 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers,
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_user},
+};
+use warp::Filter;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    get_overdue(pool.clone(), rate_limiter.clone())
+        .or(get_records(pool.clone(), rate_limiter.clone()))
+        .or(create_record(pool.clone(), rate_limiter.clone()))
+        .or(delete_record(pool.clone(), rate_limiter.clone()))
+        .or(get_windows(pool.clone(), rate_limiter.clone()))
+        .or(create_window(pool.clone(), rate_limiter.clone()))
+        .or(delete_window(pool.clone(), rate_limiter.clone()))
+        .or(get_intervals(pool.clone(), rate_limiter.clone()))
+        .or(set_intervals(pool, rate_limiter))
+}
+
+fn get_overdue(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / "maintenance" / "overdue")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::get_overdue)
+}
+
+fn get_records(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "records")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::get_records)
+}
+
+fn create_record(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "records")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::create_record)
+}
+
+fn delete_record(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "records" / i32)
+        .and(warp::delete())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::delete_record)
+}
+
+fn get_windows(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "windows")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::get_windows)
+}
+
+fn create_window(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "windows")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::create_window)
+}
+
+fn delete_window(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "windows" / i32)
+        .and(warp::delete())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::delete_window)
+}
+
+fn get_intervals(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "intervals")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::get_intervals)
+}
+
+fn set_intervals(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "maintenance" / "intervals")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::maintenance::set_intervals)
+}
@@ -0,0 +1,64 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers,
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_user},
+};
+use warp::Filter;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    get_all_marinas(pool.clone(), rate_limiter.clone())
+        .or(create_marina(pool.clone(), rate_limiter.clone()))
+        .or(get_marina(pool.clone(), rate_limiter.clone()))
+        .or(delete_marina(pool, rate_limiter))
+}
+
+fn get_marina(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("marinas" / i32)
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::marina::get_marina)
+}
+
+fn get_all_marinas(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("marinas")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::marina::get_all_marinas)
+}
+
+fn create_marina(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("marinas")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::marina::create_marina)
+}
+
+fn delete_marina(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("marinas" / i32)
+        .and(warp::delete())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_db(pool))
+        .and_then(handlers::marina::delete_marina)
+}
@@ -0,0 +1,98 @@
+use crate::{
+    db::SharedConnectionPool,
+    handlers::{self, media::MAX_FORM_SIZE},
+    rate_limiting::KeyedRateLimiter,
+    routes::filters::{process_api_key, with_db, with_storage, with_user},
+    storage::SharedStorage,
+};
+use warp::Filter;
+
+pub fn routes(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    get_media(pool.clone(), rate_limiter.clone())
+        .or(get_media_file(
+            pool.clone(),
+            rate_limiter.clone(),
+            storage.clone(),
+        ))
+        .or(get_media_thumbnail(
+            pool.clone(),
+            rate_limiter.clone(),
+            storage.clone(),
+        ))
+        .or(upload_media(
+            pool.clone(),
+            rate_limiter.clone(),
+            storage.clone(),
+        ))
+        .or(delete_media(pool, rate_limiter, storage))
+}
+
+fn get_media(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "media")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_db(pool))
+        .and_then(handlers::media::get_media)
+}
+
+fn get_media_file(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "media" / i32)
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_storage(storage))
+        .and(with_db(pool))
+        .and_then(handlers::media::get_media_file)
+}
+
+fn get_media_thumbnail(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "media" / i32 / "thumbnail")
+        .and(warp::get())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_storage(storage))
+        .and(with_db(pool))
+        .and_then(handlers::media::get_media_thumbnail)
+}
+
+fn upload_media(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "media")
+        .and(warp::post())
+        .and(warp::multipart::form().max_length(MAX_FORM_SIZE))
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_storage(storage))
+        .and(with_db(pool))
+        .and_then(handlers::media::upload_media)
+}
+
+fn delete_media(
+    pool: SharedConnectionPool,
+    rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("boats" / i32 / "media" / i32)
+        .and(warp::delete())
+        .and(process_api_key(pool.clone(), rate_limiter))
+        .and(with_user(pool.clone()))
+        .and(with_storage(storage))
+        .and(with_db(pool))
+        .and_then(handlers::media::delete_media)
+}
@@ -1,16 +1,48 @@
+pub mod batch;
 pub mod boat;
+pub mod booking;
+pub mod calendar;
 pub mod filters;
+pub mod import;
 pub mod jwt;
+pub mod maintenance;
+pub mod marina;
+pub mod media;
+pub mod pricing;
+pub mod review;
+pub mod revision;
+pub mod tag;
 pub mod user;
 
-use crate::{db::SharedConnectionPool, rate_limiting::KeyedRateLimiter, routes};
+use crate::{
+    db::SharedConnectionPool, rate_limiting::KeyedRateLimiter, routes, storage::SharedStorage,
+};
 use warp::Filter;
 
 pub fn all_routes(
     pool: SharedConnectionPool,
     rate_limiter: KeyedRateLimiter,
+    storage: SharedStorage,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    routes::boat::routes(pool.clone(), rate_limiter)
+    routes::boat::routes(pool.clone(), rate_limiter.clone(), storage.clone())
+        .or(routes::batch::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::booking::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::calendar::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::import::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::pricing::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::maintenance::routes(
+            pool.clone(),
+            rate_limiter.clone(),
+        ))
+        .or(routes::marina::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::media::routes(
+            pool.clone(),
+            rate_limiter.clone(),
+            storage,
+        ))
+        .or(routes::review::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::revision::routes(pool.clone(), rate_limiter.clone()))
+        .or(routes::tag::routes(pool.clone(), rate_limiter))
         .or(routes::user::routes(pool))
         .or(routes::jwt::routes())
 }
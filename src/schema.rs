@@ -1,3 +1,47 @@
+diesel::table! {
+    blackouts (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        uid -> Text,
+        summary -> Nullable<Text>,
+        starts_at -> TimestamptzSqlite,
+        ends_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    boat_media (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        storage_key -> Text,
+        thumbnail_key -> Nullable<Text>,
+        file_name -> Text,
+        content_type -> Text,
+        size -> BigInt,
+        checksum -> Text,
+        sort_order -> Integer,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    boat_revisions (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        action -> Text,
+        actor -> Text,
+        snapshot -> Text,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    boat_tags (boat_id, tag_id) {
+        boat_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
 diesel::table! {
     boats (id) {
         id -> Integer,
@@ -8,6 +52,122 @@ diesel::table! {
         length -> Nullable<Float>,
         beam -> Nullable<Float>,
         is_available -> Integer,
+        marina_id -> Nullable<Integer>,
+        owner_email -> Nullable<Text>,
+        deleted_at -> Nullable<TimestamptzSqlite>,
+        version -> Integer,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+        hull_type -> Nullable<Text>,
+        hull_material -> Nullable<Text>,
+        propulsion -> Nullable<Text>,
+        engine_count -> Nullable<Integer>,
+        engine_power -> Nullable<Float>,
+        fuel_type -> Nullable<Text>,
+        fuel_capacity -> Nullable<Float>,
+        water_capacity -> Nullable<Float>,
+        berths -> Nullable<Integer>,
+        cabins -> Nullable<Integer>,
+        max_passengers -> Nullable<Integer>,
+        draft -> Nullable<Float>,
+        rating -> Nullable<Double>,
+        review_count -> Integer,
+    }
+}
+
+diesel::table! {
+    bookings (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        user_email -> Text,
+        starts_at -> TimestamptzSqlite,
+        ends_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    maintenance_intervals (boat_id, service_type) {
+        boat_id -> Integer,
+        service_type -> Text,
+        every_days -> Nullable<Integer>,
+        every_engine_hours -> Nullable<Float>,
+    }
+}
+
+diesel::table! {
+    maintenance_records (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        service_type -> Text,
+        notes -> Nullable<Text>,
+        cost -> Nullable<BigInt>,
+        currency -> Nullable<Text>,
+        engine_hours -> Nullable<Float>,
+        performed_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    maintenance_windows (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        service_type -> Text,
+        notes -> Nullable<Text>,
+        starts_at -> TimestamptzSqlite,
+        ends_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    marinas (id) {
+        id -> Integer,
+        name -> Text,
+        latitude -> Double,
+        longitude -> Double,
+        timezone -> Text,
+    }
+}
+
+diesel::table! {
+    rate_cards (boat_id) {
+        boat_id -> Integer,
+        currency -> Text,
+        hourly_rate -> Nullable<BigInt>,
+        daily_rate -> Nullable<BigInt>,
+        weekly_rate -> Nullable<BigInt>,
+        min_hours -> Integer,
+    }
+}
+
+diesel::table! {
+    reviews (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        user_email -> Text,
+        rating -> Integer,
+        text -> Text,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    seasonal_rates (id) {
+        id -> Integer,
+        boat_id -> Integer,
+        name -> Text,
+        starts_on -> Date,
+        ends_on -> Date,
+        multiplier -> Double,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+        category -> Nullable<Text>,
+        description -> Nullable<Text>,
     }
 }
 
@@ -16,7 +176,40 @@ diesel::table! {
         email -> Text,
         api_key -> Text,
         credit -> Integer,
+        is_admin -> Bool,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(boats, users,);
+diesel::joinable!(blackouts -> boats (boat_id));
+diesel::joinable!(boat_media -> boats (boat_id));
+diesel::joinable!(boat_tags -> boats (boat_id));
+diesel::joinable!(boat_tags -> tags (tag_id));
+diesel::joinable!(boats -> marinas (marina_id));
+diesel::joinable!(boats -> users (owner_email));
+diesel::joinable!(bookings -> boats (boat_id));
+diesel::joinable!(bookings -> users (user_email));
+diesel::joinable!(maintenance_intervals -> boats (boat_id));
+diesel::joinable!(maintenance_records -> boats (boat_id));
+diesel::joinable!(maintenance_windows -> boats (boat_id));
+diesel::joinable!(rate_cards -> boats (boat_id));
+diesel::joinable!(reviews -> boats (boat_id));
+diesel::joinable!(reviews -> users (user_email));
+diesel::joinable!(seasonal_rates -> boats (boat_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    blackouts,
+    boat_media,
+    boat_revisions,
+    boat_tags,
+    boats,
+    bookings,
+    maintenance_intervals,
+    maintenance_records,
+    maintenance_windows,
+    marinas,
+    rate_cards,
+    reviews,
+    seasonal_rates,
+    tags,
+    users,
+);
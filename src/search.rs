@@ -0,0 +1,74 @@
+use crate::errors::Error;
+
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+// turns free text into a safe FTS5 MATCH expression: bare words become quoted terms,
+// `"..."` is kept as a phrase and a trailing `*` on either makes it a prefix query.
+// all terms must match, e.g. `beneteau "first 27"*` -> `"beneteau" "first 27"*`
+pub fn fts_query(q: &str) -> Result<String, Error> {
+    let mut terms = Vec::new();
+    let mut chars = q.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let term: String = if c == '"' {
+            chars.by_ref().take_while(|&c| c != '"').collect()
+        } else {
+            let mut word = String::from(c);
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' || c == '*' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            word
+        };
+        let prefix = chars.next_if_eq(&'*').is_some();
+        let term = term.trim().trim_end_matches('*');
+        if !term.is_empty() {
+            terms.push(format!("\"{}\"{}", term, if prefix { "*" } else { "" }));
+        }
+    }
+
+    if terms.is_empty() {
+        Err(Error::InvalidParameter)
+    } else {
+        Ok(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_words_and_keeps_phrases() {
+        assert_eq!(
+            fts_query(r#"beneteau "first 27"*"#).unwrap(),
+            r#""beneteau" "first 27"*"#
+        );
+        assert_eq!(fts_query("  sea   breeze ").unwrap(), r#""sea" "breeze""#);
+        assert_eq!(fts_query("bene*").unwrap(), r#""bene"*"#);
+    }
+
+    #[test]
+    fn neutralizes_query_syntax() {
+        assert_eq!(
+            fts_query("sea OR NOT make:x").unwrap(),
+            r#""sea" "OR" "NOT" "make:x""#
+        );
+        // an unterminated phrase runs to the end of the query
+        assert_eq!(fts_query(r#"sea "breeze"#).unwrap(), r#""sea" "breeze""#);
+        assert_eq!(fts_query(r#"a"b"#).unwrap(), r#""a" "b""#);
+    }
+
+    #[test]
+    fn rejects_queries_without_terms() {
+        for q in ["", "   ", r#""""#, "*", r#"" "*"#] {
+            assert!(fts_query(q).is_err(), "{:?}", q);
+        }
+    }
+}
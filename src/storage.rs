@@ -0,0 +1,66 @@
+use crate::errors::Error;
+use log::error;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+// backend for uploaded files, addressed by relative keys such as `boats/1/photo.jpg`
+pub trait Storage: Send + Sync {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Error>;
+    fn get(&self, key: &str) -> Result<Vec<u8>, Error>;
+    fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, Error> {
+        // keys are generated by the API, but never let one escape the storage root
+        let key = Path::new(key);
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| error!("{}", e))
+                .map_err(|_| Error::StorageFailed)?;
+        }
+        fs::write(&path, data)
+            .map_err(|e| error!("{}", e))
+            .map_err(|_| Error::StorageFailed)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        fs::read(self.path(key)?).map_err(|_| Error::NotFound)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Error> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                error!("{}", e);
+                Err(Error::StorageFailed)
+            }
+            _ => Ok(()),
+        }
+    }
+}
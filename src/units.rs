@@ -0,0 +1,146 @@
+use crate::errors::Error;
+use std::str::FromStr;
+use warp::{http::header::VARY, reply, Reply};
+
+// names the measurement system of the dimensions in a response
+pub const CONTENT_UNITS_HEADER: &str = "content-units";
+pub const ACCEPT_UNITS_HEADER: &str = "accept-units";
+
+// measurement system of the dimensions in a request or response. Boats are stored in metric
+// units, imperial values are in feet, US gallons and horsepower
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+#[derive(Clone, Copy)]
+pub enum Quantity {
+    // metres or feet
+    Length,
+    // litres or US gallons
+    Volume,
+    // kilowatts or horsepower
+    Power,
+}
+
+impl Quantity {
+    fn metric_per_imperial(self) -> f64 {
+        match self {
+            Quantity::Length => 0.3048,
+            Quantity::Volume => 3.785_411_784,
+            Quantity::Power => 0.745_699_872,
+        }
+    }
+
+    // decimals kept of a converted metric value, well below what the imperial value can
+    // express, so that values survive being read and written back unchanged
+    fn metric_decimals(self) -> i32 {
+        match self {
+            Quantity::Length => 3,
+            Quantity::Volume => 1,
+            Quantity::Power => 2,
+        }
+    }
+}
+
+impl Units {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+        }
+    }
+
+    // converts a value sent in these units to a stored metric one
+    pub fn to_metric(self, quantity: Quantity, value: f32) -> f32 {
+        match self {
+            Units::Metric => value,
+            Units::Imperial => {
+                let scale = 10f64.powi(quantity.metric_decimals());
+                ((f64::from(value) * quantity.metric_per_imperial() * scale).round() / scale) as f32
+            }
+        }
+    }
+
+    // expresses a stored metric value in these units
+    pub fn express(self, quantity: Quantity, value: f32) -> f32 {
+        match self {
+            Units::Metric => value,
+            Units::Imperial => (f64::from(value) / quantity.metric_per_imperial()) as f32,
+        }
+    }
+}
+
+impl FromStr for Units {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "metric" => Ok(Units::Metric),
+            "imperial" => Ok(Units::Imperial),
+            _ => Err(Error::InvalidParameter),
+        }
+    }
+}
+
+// labels the units of a response, which vary with the Accept-Units header
+pub fn label_units(reply: impl Reply, units: Units) -> impl Reply {
+    reply::with_header(
+        reply::with_header(reply, CONTENT_UNITS_HEADER, units.as_str()),
+        VARY,
+        ACCEPT_UNITS_HEADER,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUANTITIES: [Quantity; 3] = [Quantity::Length, Quantity::Volume, Quantity::Power];
+
+    #[test]
+    fn parses_unit_names() {
+        assert!("metric".parse::<Units>().unwrap() == Units::Metric);
+        assert!("imperial".parse::<Units>().unwrap() == Units::Imperial);
+        assert!(" Imperial".parse::<Units>().unwrap() == Units::Imperial);
+        assert!("us".parse::<Units>().is_err());
+        assert!("".parse::<Units>().is_err());
+    }
+
+    #[test]
+    fn leaves_metric_values_alone() {
+        for quantity in QUANTITIES {
+            assert_eq!(Units::Metric.to_metric(quantity, 12.345_67), 12.345_67);
+            assert_eq!(Units::Metric.express(quantity, 12.345_67), 12.345_67);
+        }
+    }
+
+    #[test]
+    fn converts_imperial_values() {
+        assert_eq!(Units::Imperial.to_metric(Quantity::Length, 40.0), 12.192);
+        assert_eq!(Units::Imperial.to_metric(Quantity::Volume, 100.0), 378.5);
+        assert_eq!(Units::Imperial.to_metric(Quantity::Power, 300.0), 223.71);
+        assert!((Units::Imperial.express(Quantity::Length, 12.192) - 40.0).abs() < 1e-4);
+        assert!((Units::Imperial.express(Quantity::Power, 223.71) - 300.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn stores_values_read_and_written_back_unchanged() {
+        for quantity in QUANTITIES {
+            for value in [0.5, 1.0, 3.3, 12.75, 41.2, 99.9, 523.4, 4_000.0, 120_000.0] {
+                let stored = Units::Imperial.to_metric(quantity, value);
+                let read = Units::Imperial.express(quantity, stored);
+                assert_eq!(Units::Imperial.to_metric(quantity, read), stored);
+            }
+        }
+    }
+
+    #[test]
+    fn labels_responses_with_their_units() {
+        let response = label_units(reply::reply(), Units::Imperial).into_response();
+        assert_eq!(response.headers()[CONTENT_UNITS_HEADER], "imperial");
+        assert_eq!(response.headers()[VARY], ACCEPT_UNITS_HEADER);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::errors::Error;
+use serde::Serialize;
+use std::{borrow::Cow, fmt::Display};
+
+// machine readable codes of field errors
+pub const REQUIRED: &str = "required";
+pub const TOO_LONG: &str = "too_long";
+pub const OUT_OF_RANGE: &str = "out_of_range";
+pub const INVALID_VALUE: &str = "invalid_value";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FieldError {
+    pub field: Cow<'static, str>,
+    pub code: &'static str,
+    pub message: String,
+}
+
+// collects every field error of a value instead of stopping at the first one
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn error(
+        &mut self,
+        field: impl Into<Cow<'static, str>>,
+        code: &'static str,
+        message: String,
+    ) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            code,
+            message,
+        });
+    }
+
+    pub fn text(&mut self, field: &'static str, value: &str, max_length: usize) {
+        if value.is_empty() {
+            self.error(field, REQUIRED, format!("{} must not be empty", field));
+        } else if value.chars().count() > max_length {
+            self.error(
+                field,
+                TOO_LONG,
+                format!("{} must be at most {} characters", field, max_length),
+            );
+        }
+    }
+
+    pub fn range<T: PartialOrd + Display>(
+        &mut self,
+        field: &'static str,
+        value: T,
+        min: T,
+        max: T,
+    ) {
+        // written so that NaN is out of every range
+        if !(value >= min && value <= max) {
+            self.error(
+                field,
+                OUT_OF_RANGE,
+                format!("{} must be between {} and {}", field, min, max),
+            );
+        }
+    }
+
+    pub fn one_of<T: PartialEq + Display>(&mut self, field: &'static str, value: T, allowed: &[T]) {
+        if !allowed.contains(&value) {
+            let allowed: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+            self.error(
+                field,
+                INVALID_VALUE,
+                format!("{} must be one of {}", field, allowed.join(", ")),
+            );
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Error> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Validation(self.errors))
+        }
+    }
+}